@@ -47,6 +47,9 @@ function bindClick(id, handler) {
 }
 
 // ==================== 局域网扫描 (提前定义) ====================
+let scanResults = [];
+let scanning = false;
+
 async function scanDevices() {
     console.log('点击扫描按钮');
     showMessage('正在启动扫描...');
@@ -62,18 +65,22 @@ async function scanDevices() {
     const statusText = $('scan-status-text');
     const spinner = $('scan-spinner');
 
+    scanResults = [];
+    scanning = true;
     if (list) list.innerHTML = '';
     if (statusText) statusText.textContent = '正在全速扫描局域网 (无需 Root)...';
     if (spinner) spinner.style.display = 'block';
 
     try {
         console.log('调用后端 invoke scan_tcp_devices');
+        // 发现的设备通过 scan-found 事件实时显示，结束后按地址排序
         const devices = await invoke('scan_tcp_devices');
         console.log('扫描完成，结果:', devices);
+        scanResults = devices;
         renderScanResults(devices);
 
         if (devices.length === 0) {
-            if (statusText) statusText.textContent = '未发现开启 5555 端口的设备';
+            if (statusText) statusText.textContent = '未发现设备';
         } else {
             if (statusText) statusText.textContent = `发现 ${devices.length} 个设备`;
         }
@@ -82,10 +89,24 @@ async function scanDevices() {
         if (statusText) statusText.textContent = `扫描出错: ${e}`;
         showMessage(`扫描失败: ${e}`);
     } finally {
+        scanning = false;
         if (spinner) spinner.style.display = 'none';
     }
 }
 
+function onScanFound(device) {
+    if (!scanning) return;
+    scanResults.push(device);
+    renderScanResults(scanResults);
+    const statusText = $('scan-status-text');
+    if (statusText) statusText.textContent = `正在扫描... 已发现 ${scanResults.length} 个设备`;
+}
+
+function closeScanModal() {
+    if (scanning) invoke('scan_cancel');
+    hideModal('scan-results-modal');
+}
+
 function renderScanResults(devices) {
     const list = $('scan-results-list');
     if (!list) return;
@@ -95,11 +116,11 @@ function renderScanResults(devices) {
         return;
     }
 
-    list.innerHTML = devices.map(ip => `
-        <div class="device-item" onclick="selectScanDevice('${ip}')">
+    list.innerHTML = devices.map(d => `
+        <div class="device-item" onclick="selectScanDevice('${d.ip}', ${d.port}, ${d.pairing})">
             <div>
-                <div class="device-ip">${ip}</div>
-                <div class="device-hint">端口: 5555</div>
+                <div class="device-ip">${d.model || d.service || d.ip}</div>
+                <div class="device-hint">${d.ip}:${d.port}${scanResultHint(d)}</div>
             </div>
             <svg viewBox="0 0 24 24" width="20" height="20" stroke="currentColor" stroke-width="2" fill="none" stroke-linecap="round" stroke-linejoin="round">
                 <path d="M5 12h14M12 5l7 7-7 7"/>
@@ -108,11 +129,27 @@ function renderScanResults(devices) {
     `).join('');
 }
 
-async function selectScanDevice(ip) {
-    hideModal('scan-results-modal');
+function scanResultHint(d) {
+    if (d.pairing) return ' · 等待配对';
+    if (d.service) return ' · 无线调试';
+    if (d.auth_required) return ' · 需要在手机上授权';
+    return d.product ? ` · ${d.product}` : '';
+}
+
+async function selectScanDevice(ip, port, pairing) {
+    closeScanModal();
+
+    // 配对服务：预填配对地址，等待输入配对码
+    if (pairing) {
+        $('pair-ip').value = `${ip}:${port}`;
+        showModal('pair-modal');
+        $('pair-code').focus();
+        return;
+    }
+
     const input = $('ip-input');
-    if (input) input.value = ip + ":5555";
-    showMessage(`已选择设备: ${ip}`);
+    if (input) input.value = `${ip}:${port}`;
+    showMessage(`已选择设备: ${ip}:${port}`);
 
     // 自动连接
     await connectWireless();
@@ -134,6 +171,7 @@ document.addEventListener('DOMContentLoaded', async () => {
         loadAppDatabase(); // Load silently in background
         setupEventListeners();
         initUI();
+        checkBinaries();
         console.log('App Initialized Successfully');
     } catch (e) {
         console.error('App Initialization Failed:', e);
@@ -141,6 +179,17 @@ document.addEventListener('DOMContentLoaded', async () => {
     }
 });
 
+// 检查 adb / scrcpy 是否可用
+async function checkBinaries() {
+    try {
+        const statuses = await invoke('binary_status');
+        const missing = statuses.filter(s => !s.binary).map(s => s.error);
+        if (missing.length > 0) showMessage(missing.join('\n'));
+    } catch (e) {
+        console.warn('检查 adb/scrcpy 失败:', e);
+    }
+}
+
 // 加载配置
 async function loadConfig() {
     try {
@@ -197,7 +246,8 @@ function setupEventListeners() {
     bindClick('kill-btn', killScrcpy);
     bindClick('push-file-btn', pushFile);
     bindClick('pair-btn', openPairModal);
-    bindClick('pair-cancel-btn', () => hideModal('pair-modal'));
+    bindClick('pair-cancel-btn', closePairModal);
+    bindClick('qr-pair-btn', startQrPair);
     bindClick('pair-confirm-btn', pairDevice);
 
     // 新增：扫描功能
@@ -205,7 +255,7 @@ function setupEventListeners() {
         console.log('Scan button clicked via safe bind');
         scanDevices();
     });
-    bindClick('scan-cancel-btn', () => hideModal('scan-results-modal'));
+    bindClick('scan-cancel-btn', closeScanModal);
 
     // 标签页
     document.querySelectorAll('.tab').forEach(tab => {
@@ -265,13 +315,19 @@ function setupEventListeners() {
     }
 
     const useCustomArgs = $('use-custom-args');
-    if (useCustomArgs) useCustomArgs.onchange = toggleCustomArgsMode;
+    if (useCustomArgs) useCustomArgs.onchange = async () => {
+        await switchArgsMode('opt', 'use-custom-args', 'custom-args');
+        toggleCustomArgsMode();
+    };
 
     const useAppStreamArgs = $('use-app-stream-args');
     if (useAppStreamArgs) useAppStreamArgs.onchange = toggleAppStreamArgs;
 
     const useAppCustomArgs = $('use-app-custom-args');
-    if (useAppCustomArgs) useAppCustomArgs.onchange = toggleAppCustomArgsMode;
+    if (useAppCustomArgs) useAppCustomArgs.onchange = async () => {
+        await switchArgsMode('app-opt', 'use-app-custom-args', 'app-custom-args');
+        toggleAppCustomArgsMode();
+    };
 
     bindClick('advanced-defaults-btn', restoreDefaults);
     bindClick('advanced-cancel-btn', () => hideModal('advanced-modal'));
@@ -341,6 +397,38 @@ function setupEventListeners() {
         updateProgressBar(progress, message);
     });
 
+    listen('adb-install-progress', (event) => {
+        const { progress, message } = event.payload;
+        updateProgressBar(progress, message);
+    });
+
+    listen('qr-pair-progress', (event) => onQrPairProgress(event.payload));
+    listen('scan-found', (event) => onScanFound(event.payload));
+
+    // scrcpy 异常退出时提示原因
+    listen('session-ended', (event) => {
+        const { serial, exit_code, stopped, stderr } = event.payload;
+        if (!stopped && exit_code !== 0) {
+            const reason = stderr ? stderr.split('\n').pop() : `退出代码: ${exit_code}`;
+            showMessage(`Scrcpy 已退出 (${serial}): ${reason}`);
+        }
+    });
+
+    // 设备选择 (托盘菜单中切换时同步)
+    const deviceSelect = $('device-select');
+    if (deviceSelect) deviceSelect.addEventListener('change', (e) => selectDevice(e.target.value));
+    listen('active-device-changed', (event) => {
+        config.active_device = event.payload;
+        installedPackages.clear();
+        isInstalledAppsSynced = false;
+        checkAdbStatus();
+    });
+
+    // 监听设备变化 (后台 host:track-devices 推送)
+    for (const name of ['device-added', 'device-removed', 'device-state-changed']) {
+        listen(name, () => checkAdbStatus());
+    }
+
     // 监听文件拖拽
     setupFileDropListeners();
 }
@@ -376,6 +464,7 @@ async function setupFileDropListeners() {
 
 async function pushFileFromPath(path) {
     if (!path) return;
+    if (/\.(apk|apks|xapk|apkm)$/i.test(path)) return installApkFromPath(path);
 
     setLoading('push-file-btn', true);
     // 重置并显示进度条 (追加模式或者单文件模式)
@@ -393,6 +482,27 @@ async function pushFileFromPath(path) {
     }
 }
 
+async function installApkFromPath(path, options = { replace: true }) {
+    setLoading('push-file-btn', true);
+    updateProgressBar(0, `准备安装 ${path.split(/[\\/]/).pop()}...`);
+
+    try {
+        const result = await invoke('adb_install_apk', { apkPath: path, options });
+        showMessage(result.message);
+
+        // 版本降级时询问是否允许降级重装
+        if (!result.success && result.error && result.error.kind === 'version_downgrade' && !options.downgrade) {
+            if (confirm('设备上已安装更高版本，是否降级安装？')) {
+                await installApkFromPath(path, { ...options, downgrade: true });
+            }
+        }
+    } catch (e) {
+        showMessage(`安装失败: ${e}`);
+    } finally {
+        setLoading('push-file-btn', false);
+    }
+}
+
 function updateProgressBar(progress, message) {
     const container = $('push-progress');
     const fill = $('progress-fill');
@@ -415,11 +525,40 @@ function updateProgressBar(progress, message) {
 
 // ==================== ADB 操作 ====================
 
+// 多台设备时显示设备选择框
+function renderDeviceSelect(devices) {
+    const select = $('device-select');
+    if (!select) return;
+
+    const ready = devices.filter(d => d.state === 'device');
+    select.classList.toggle('hidden', ready.length < 2);
+    select.innerHTML = '<option value="">选择设备...</option>' + ready.map(d => {
+        const name = d.model ? `${d.model.replace(/_/g, ' ')} (${d.serial})` : d.serial;
+        const server = d.server && d.server !== 'local' ? ` @${d.server}` : '';
+        return `<option value="${d.serial}">${name}${server}</option>`;
+    }).join('');
+    select.value = ready.some(d => d.serial === config.active_device) ? config.active_device : '';
+}
+
+async function selectDevice(serial) {
+    try {
+        await invoke('set_active_device', { serial: serial || null });
+        config.active_device = serial || null;
+        // 切换设备后重新同步已安装应用
+        installedPackages.clear();
+        isInstalledAppsSynced = false;
+        checkAdbStatus();
+    } catch (e) {
+        showMessage('切换设备失败: ' + e);
+    }
+}
+
 async function checkAdbStatus() {
     try {
         const status = await invoke('check_adb_status');
         $('status-dot').classList.toggle('connected', status.connected);
         $('status-text').textContent = status.message;
+        renderDeviceSelect(status.devices || []);
 
         // 智能补全状态同步
         if (status.connected) {
@@ -593,7 +732,7 @@ async function pairDevice() {
     try {
         const result = await invoke('pair_device', { addr, code });
         if (result.success) {
-            hideModal('pair-modal');
+            closePairModal();
             showMessage(result.message);
             // 提示用户连接
             const connectIp = addr.split(':')[0]; // 这里配对端口通常不是连接端口
@@ -611,6 +750,49 @@ async function pairDevice() {
     }
 }
 
+// ==================== 扫码配对 ====================
+
+let qrPairName = null;
+
+async function startQrPair() {
+    try {
+        const pairing = await invoke('qr_pair_start');
+        qrPairName = pairing.name;
+        $('qr-pair-image').src = pairing.qr;
+        $('qr-pair-status').textContent = '请在 "无线调试" -> "使用二维码配对设备" 中扫码';
+        $('qr-pair-panel').style.display = 'block';
+    } catch (e) {
+        showMessage(`生成二维码失败: ${e}`);
+    }
+}
+
+function onQrPairProgress({ name, stage, message }) {
+    if (name !== qrPairName) return;
+    const status = $('qr-pair-status');
+    if (status) status.textContent = message;
+
+    if (stage === 'connected') {
+        qrPairName = null;
+        hideModal('pair-modal');
+        $('qr-pair-panel').style.display = 'none';
+        showMessage(`扫码配对成功，${message}`);
+        loadConfig().then(loadHistoryDropdown);
+        checkAdbStatus();
+    } else if (stage === 'failed') {
+        qrPairName = null;
+        showMessage(`扫码配对失败: ${message}`);
+    }
+}
+
+function closePairModal() {
+    if (qrPairName) {
+        invoke('qr_pair_cancel');
+        qrPairName = null;
+    }
+    $('qr-pair-panel').style.display = 'none';
+    hideModal('pair-modal');
+}
+
 // ==================== 启动 Scrcpy ====================
 
 async function launchMirror() {
@@ -1344,17 +1526,8 @@ function openAdvancedModal() {
     $('custom-args').value = config.custom_args;
     toggleCustomArgsMode();
 
-    const opts = config.scrcpy_options;
-    $('opt-hid').checked = opts.hid_keyboard;
-    $('opt-awake').checked = opts.stay_awake;
-    $('opt-screen-off').checked = opts.turn_screen_off;
-    $('opt-touches').checked = opts.show_touches;
-    $('opt-top').checked = opts.always_on_top;
-    $('opt-borderless').checked = opts.borderless;
-    $('opt-power-off').checked = opts.power_off_on_close;
-    $('opt-ime').checked = opts.local_ime;
-    $('opt-max-size').value = opts.max_size || '';
-    $('opt-max-fps').value = opts.max_fps || '';
+    advancedOptions = { opt: { ...config.scrcpy_options }, 'app-opt': { ...config.app_stream_options } };
+    fillOptionsForm('opt', advancedOptions.opt);
 
     // 根据配置开启/关闭选项
     if (config.use_custom_args) {
@@ -1378,21 +1551,87 @@ function openAdvancedModal() {
     toggleAppStreamArgs();
     toggleAppCustomArgsMode();
 
-    const appOpts = config.app_stream_options;
-    $('app-opt-hid').checked = appOpts.hid_keyboard;
-    $('app-opt-awake').checked = appOpts.stay_awake;
-    $('app-opt-screen-off').checked = appOpts.turn_screen_off;
-    $('app-opt-touches').checked = appOpts.show_touches;
-    $('app-opt-top').checked = appOpts.always_on_top;
-    $('app-opt-borderless').checked = appOpts.borderless;
-    $('app-opt-power-off').checked = appOpts.power_off_on_close;
-    $('app-opt-ime').checked = appOpts.local_ime;
-    $('app-opt-max-size').value = appOpts.max_size || '';
-    $('app-opt-max-fps').value = appOpts.max_fps || '';
+    fillOptionsForm('app-opt', advancedOptions['app-opt']);
 
     showModal('advanced-modal');
 }
 
+// 高级设置中正在编辑的结构化选项 (包含界面未列出的字段，如编码、窗口和导入的其它参数)
+let advancedOptions = { opt: {}, 'app-opt': {} };
+
+function fillOptionsForm(prefix, opts) {
+    $(`${prefix}-hid`).checked = opts.hid_keyboard;
+    $(`${prefix}-awake`).checked = opts.stay_awake;
+    $(`${prefix}-screen-off`).checked = opts.turn_screen_off;
+    $(`${prefix}-touches`).checked = opts.show_touches;
+    $(`${prefix}-top`).checked = opts.always_on_top;
+    $(`${prefix}-borderless`).checked = opts.borderless;
+    $(`${prefix}-power-off`).checked = opts.power_off_on_close;
+    $(`${prefix}-ime`).checked = opts.local_ime;
+    $(`${prefix}-max-size`).value = opts.max_size || '';
+    $(`${prefix}-max-fps`).value = opts.max_fps || '';
+}
+
+function readOptionsForm(prefix) {
+    return {
+        ...advancedOptions[prefix],
+        hid_keyboard: $(`${prefix}-hid`).checked,
+        stay_awake: $(`${prefix}-awake`).checked,
+        turn_screen_off: $(`${prefix}-screen-off`).checked,
+        show_touches: $(`${prefix}-touches`).checked,
+        always_on_top: $(`${prefix}-top`).checked,
+        borderless: $(`${prefix}-borderless`).checked,
+        power_off_on_close: $(`${prefix}-power-off`).checked,
+        local_ime: $(`${prefix}-ime`).checked,
+        max_size: parseInt($(`${prefix}-max-size`).value) || 0,
+        max_fps: parseInt($(`${prefix}-max-fps`).value) || 0,
+    };
+}
+
+// 切换自定义参数/结构化选项时互相转换，避免参数丢失
+async function switchArgsMode(prefix, checkboxId, textareaId) {
+    const useCustom = $(checkboxId).checked;
+    try {
+        if (useCustom) {
+            $(textareaId).value = await invoke('export_scrcpy_args', { options: readOptionsForm(prefix) });
+        } else {
+            const result = await invoke('import_scrcpy_args', { commandLine: $(textareaId).value });
+            advancedOptions[prefix] = result.options;
+            fillOptionsForm(prefix, result.options);
+            if (result.unmapped.length > 0) {
+                showMessage(`以下参数没有对应的选项，将原样保留: ${result.unmapped.join(' ')}`);
+            }
+        }
+    } catch (e) {
+        // 参数格式错误时留在自定义模式
+        $(checkboxId).checked = true;
+        showMessage(`参数转换失败: ${e}`);
+    }
+}
+
+// 检查镜像和应用流转参数是否被当前 scrcpy 支持，返回提示文字 (全部支持时为空)
+async function unsupportedArgs() {
+    const scopes = [['opt', 'use-custom-args', 'custom-args']];
+    if ($('use-app-stream-args').checked) scopes.push(['app-opt', 'use-app-custom-args', 'app-custom-args']);
+    const warnings = new Map();
+    let version = null;
+    try {
+        for (const [prefix, checkboxId, textareaId] of scopes) {
+            const commandLine = $(checkboxId).checked
+                ? $(textareaId).value
+                : await invoke('export_scrcpy_args', { options: readOptionsForm(prefix) });
+            const report = await invoke('check_scrcpy_compat', { commandLine });
+            version = report.version;
+            report.warnings.forEach(w => warnings.set(w.flag, w.min_version));
+        }
+    } catch (e) {
+        return '';
+    }
+    if (warnings.size === 0) return '';
+    const list = [...warnings].map(([flag, min]) => `${flag} (需要 ${min}+)`).join('、');
+    return `当前 scrcpy ${version} 不支持: ${list}`;
+}
+
 function toggleCustomArgsMode() {
     const useCustom = $('use-custom-args').checked;
     $('global-options-grid').classList.toggle('hidden', useCustom);
@@ -1414,16 +1653,8 @@ function restoreDefaults() {
     $('custom-args').value = '-K --stay-awake';
     toggleCustomArgsMode();
 
-    $('opt-hid').checked = true;
-    $('opt-awake').checked = true;
-    $('opt-screen-off').checked = false;
-    $('opt-touches').checked = false;
-    $('opt-top').checked = false;
-    $('opt-borderless').checked = false;
-    $('opt-power-off').checked = false;
-    $('opt-ime').checked = false;
-    $('opt-max-size').value = '';
-    $('opt-max-fps').value = '';
+    advancedOptions = { opt: {}, 'app-opt': {} };
+    fillOptionsForm('opt', { hid_keyboard: true, stay_awake: true });
     $('opt-filter-installed').checked = true; // Default to true
 
     $('use-app-stream-args').checked = true;
@@ -1432,44 +1663,12 @@ function restoreDefaults() {
     toggleAppStreamArgs();
     toggleAppCustomArgsMode();
 
-    $('app-opt-hid').checked = true;
-    $('app-opt-awake').checked = true;
-    $('app-opt-screen-off').checked = false;
-    $('app-opt-touches').checked = false;
-    $('app-opt-top').checked = false;
-    $('app-opt-borderless').checked = false;
-    $('app-opt-power-off').checked = false;
-    $('app-opt-ime').checked = true;
-    $('app-opt-max-size').value = '';
-    $('app-opt-max-fps').value = '';
+    fillOptionsForm('app-opt', { hid_keyboard: true, stay_awake: true, local_ime: true });
 }
 
 async function saveAdvancedSettings() {
-    const options = {
-        hid_keyboard: $('opt-hid').checked,
-        stay_awake: $('opt-awake').checked,
-        turn_screen_off: $('opt-screen-off').checked,
-        show_touches: $('opt-touches').checked,
-        always_on_top: $('opt-top').checked,
-        borderless: $('opt-borderless').checked,
-        power_off_on_close: $('opt-power-off').checked,
-        local_ime: $('opt-ime').checked,
-        max_size: parseInt($('opt-max-size').value) || 0,
-        max_fps: parseInt($('opt-max-fps').value) || 0,
-    };
-
-    const appOptions = {
-        hid_keyboard: $('app-opt-hid').checked,
-        stay_awake: $('app-opt-awake').checked,
-        turn_screen_off: $('app-opt-screen-off').checked,
-        show_touches: $('app-opt-touches').checked,
-        always_on_top: $('app-opt-top').checked,
-        borderless: $('app-opt-borderless').checked,
-        power_off_on_close: $('app-opt-power-off').checked,
-        local_ime: $('app-opt-ime').checked,
-        max_size: parseInt($('app-opt-max-size').value) || 0,
-        max_fps: parseInt($('app-opt-max-fps').value) || 0,
-    };
+    const options = readOptionsForm('opt');
+    const appOptions = readOptionsForm('app-opt');
 
     try {
         await invoke('save_scrcpy_options', {
@@ -1483,7 +1682,8 @@ async function saveAdvancedSettings() {
         });
         await loadConfig();
         hideModal('advanced-modal');
-        showMessage('高级设置已保存！');
+        const unsupported = await unsupportedArgs();
+        showMessage(unsupported ? `高级设置已保存，但${unsupported}` : '高级设置已保存！');
     } catch (e) {
         showMessage(`保存失败: ${e}`);
     }
@@ -1584,79 +1784,6 @@ window.openPresetEditModal = openPresetEditModal;
 window.deletePreset = deletePreset;
 window.selectHistory = selectHistory;
 
-// ==================== 局域网扫描 ====================
-
-async function scanDevices() {
-    console.log('点击扫描按钮');
-    showMessage('正在启动扫描...');
-    try {
-        showModal('scan-results-modal');
-    } catch (e) {
-        console.error('显示弹窗失败:', e);
-        showMessage('显示弹窗失败: ' + e);
-        return;
-    }
-
-    const list = $('scan-results-list');
-    const statusText = $('scan-status-text');
-    const spinner = $('scan-spinner');
-
-    list.innerHTML = '';
-    statusText.textContent = '正在全速扫描局域网 (无需 Root)...';
-    spinner.style.display = 'block';
-
-    try {
-        console.log('调用后端 invoke scan_tcp_devices');
-        const devices = await invoke('scan_tcp_devices');
-        console.log('扫描完成，结果:', devices);
-        renderScanResults(devices);
-
-        if (devices.length === 0) {
-            statusText.textContent = '未发现开启 5555 端口的设备';
-        } else {
-            statusText.textContent = `发现 ${devices.length} 个设备`;
-        }
-    } catch (e) {
-        console.error('扫描出错:', e);
-        statusText.textContent = `扫描出错: ${e}`;
-        showMessage(`扫描失败: ${e}`);
-    } finally {
-        spinner.style.display = 'none';
-    }
-}
-
-function renderScanResults(devices) {
-    const list = $('scan-results-list');
-    if (devices.length === 0) {
-        list.innerHTML = '<div style="padding: 20px; text-align: center; color: var(--text-secondary);">暂无发现<br><small>请确保手机已连接同一 Wi-Fi 并开启了"无线调试"或已通过 USB 执行过 `adb tcpip 5555`</small></div>';
-        return;
-    }
-
-    list.innerHTML = devices.map(ip => `
-        <div class="device-item" onclick="selectScanDevice('${ip}')">
-            <div>
-                <div class="device-ip">${ip}</div>
-                <div class="device-hint">端口: 5555</div>
-            </div>
-            <svg viewBox="0 0 24 24" width="20" height="20" stroke="currentColor" stroke-width="2" fill="none" stroke-linecap="round" stroke-linejoin="round">
-                <path d="M5 12h14M12 5l7 7-7 7"/>
-            </svg>
-        </div>
-    `).join('');
-}
-
-async function selectScanDevice(ip) {
-    hideModal('scan-results-modal');
-    $('ip-input').value = ip + ":5555";
-    showMessage(`已选择设备: ${ip}`);
-
-    // 自动连接
-    await connectWireless();
-}
-
-window.selectScanDevice = selectScanDevice;
-window.scanDevices = scanDevices;
-
 // ==================== 应用名自动补全 ====================
 // 应用名输入建议
 function handleAppNameInput() {
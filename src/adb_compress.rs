@@ -0,0 +1,257 @@
+//! Scrcpy Launcher - SYNC v2 压缩模块
+//!
+//! 为 SND2/RCV2 提供 brotli / lz4 / zstd 流式编解码。
+//! 编解码在阻塞线程中进行，通过通道与异步的 SYNC 连接交换数据块。
+
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// 单个 DATA 块的最大长度
+pub const MAX_CHUNK: usize = 64 * 1024;
+
+/// SYNC v2 支持的压缩算法
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Brotli,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    /// SND2/RCV2 请求中的标志位
+    pub fn flag(self) -> u32 {
+        match self {
+            Compression::None => 0,
+            Compression::Brotli => 1,
+            Compression::Lz4 => 2,
+            Compression::Zstd => 4,
+        }
+    }
+
+    /// 对应的设备特性名
+    fn feature(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Brotli => Some("sendrecv_v2_brotli"),
+            Compression::Lz4 => Some("sendrecv_v2_lz4"),
+            Compression::Zstd => Some("sendrecv_v2_zstd"),
+        }
+    }
+
+    /// 按 zstd > lz4 > brotli 的优先级选择设备支持的算法
+    pub fn negotiate(features: &[String]) -> Self {
+        [Compression::Zstd, Compression::Lz4, Compression::Brotli]
+            .into_iter()
+            .find(|c| c.feature().is_some_and(|f| features.iter().any(|x| x == f)))
+            .unwrap_or(Compression::None)
+    }
+}
+
+/// 在阻塞线程中读取并压缩本地文件，压缩后的数据块通过通道输出
+///
+/// 返回 (数据块接收端, 已读取的原始字节数, 线程句柄)。
+/// 接收端被丢弃时线程会以 BrokenPipe 结束。
+pub fn spawn_encoder(
+    file: std::fs::File,
+    compression: Compression,
+) -> (mpsc::Receiver<Vec<u8>>, Arc<AtomicU64>, JoinHandle<io::Result<()>>) {
+    let (tx, rx) = mpsc::channel(4);
+    let counter = Arc::new(AtomicU64::new(0));
+    let read_count = counter.clone();
+
+    let handle = tokio::task::spawn_blocking(move || {
+        let mut reader = CountingReader { inner: file, count: read_count };
+        let mut sink = ChannelWriter { tx, buf: Vec::with_capacity(MAX_CHUNK) };
+        encode(&mut reader, &mut sink, compression)?;
+        sink.send_buffered()
+    });
+
+    (rx, counter, handle)
+}
+
+/// 在阻塞线程中解压数据块并写入本地文件
+///
+/// 返回 (数据块发送端, 已写入的原始字节数, 线程句柄)。
+/// 丢弃发送端表示数据结束。
+pub fn spawn_decoder(
+    file: std::fs::File,
+    compression: Compression,
+) -> (mpsc::Sender<Vec<u8>>, Arc<AtomicU64>, JoinHandle<io::Result<()>>) {
+    let (tx, rx) = mpsc::channel(4);
+    let counter = Arc::new(AtomicU64::new(0));
+    let write_count = counter.clone();
+
+    let handle = tokio::task::spawn_blocking(move || {
+        let mut source = ChannelReader { rx, buf: Vec::new(), pos: 0 };
+        let mut writer = CountingWriter { inner: file, count: write_count };
+        decode(&mut source, &mut writer, compression)?;
+        writer.flush()
+    });
+
+    (tx, counter, handle)
+}
+
+fn encode<R: Read, W: Write>(reader: &mut R, sink: &mut W, compression: Compression) -> io::Result<()> {
+    match compression {
+        Compression::None => {
+            io::copy(reader, sink)?;
+        }
+        Compression::Brotli => {
+            let mut encoder = brotli::CompressorWriter::new(sink, MAX_CHUNK, 5, 22);
+            io::copy(reader, &mut encoder)?;
+            // into_inner 会写出流结束标记
+            encoder.into_inner();
+        }
+        Compression::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(sink);
+            io::copy(reader, &mut encoder)?;
+            encoder.finish().map_err(io::Error::other)?;
+        }
+        Compression::Zstd => {
+            let mut encoder = zstd::stream::write::Encoder::new(sink, 3)?;
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(())
+}
+
+fn decode<R: Read, W: Write>(source: &mut R, writer: &mut W, compression: Compression) -> io::Result<()> {
+    match compression {
+        Compression::None => io::copy(source, writer)?,
+        Compression::Brotli => io::copy(&mut brotli::Decompressor::new(source, MAX_CHUNK), writer)?,
+        Compression::Lz4 => io::copy(&mut lz4_flex::frame::FrameDecoder::new(source), writer)?,
+        Compression::Zstd => io::copy(&mut zstd::stream::read::Decoder::new(source)?, writer)?,
+    };
+    Ok(())
+}
+
+/// 统计读取字节数的 Reader
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/// 统计写入字节数的 Writer
+struct CountingWriter<W> {
+    inner: W,
+    count: Arc<AtomicU64>,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 把写入的数据按 MAX_CHUNK 切块发送到通道
+struct ChannelWriter {
+    tx: mpsc::Sender<Vec<u8>>,
+    buf: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn send_buffered(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(MAX_CHUNK));
+        self.tx
+            .blocking_send(chunk)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "传输已中断"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = data.len().min(MAX_CHUNK - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == MAX_CHUNK {
+            self.send_buffered()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 从通道读取数据块的 Reader，发送端关闭即 EOF
+struct ChannelReader {
+    rx: mpsc::Receiver<Vec<u8>>,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => {
+                    self.buf = chunk;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let features = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(Compression::negotiate(&features(&["sendrecv_v2"])), Compression::None);
+        assert_eq!(
+            Compression::negotiate(&features(&["sendrecv_v2", "sendrecv_v2_brotli", "sendrecv_v2_lz4"])),
+            Compression::Lz4
+        );
+        assert_eq!(
+            Compression::negotiate(&features(&["sendrecv_v2_brotli", "sendrecv_v2_zstd"])),
+            Compression::Zstd
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..200_000u32).flat_map(|i| (i % 251).to_le_bytes()).collect();
+
+        for compression in [Compression::None, Compression::Brotli, Compression::Lz4, Compression::Zstd] {
+            let mut encoded = Vec::new();
+            encode(&mut data.as_slice(), &mut encoded, compression).unwrap();
+
+            let mut decoded = Vec::new();
+            decode(&mut encoded.as_slice(), &mut decoded, compression).unwrap();
+            assert_eq!(decoded, data, "{:?}", compression);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Scrcpy Launcher - 端口转发模块
+//!
+//! 通过 ADB Server 管理 `adb forward` / `adb reverse` 规则：
+//! - forward: `host-serial:<s>:forward` / `list-forward` / `killforward`，电脑端口 -> 设备端口
+//! - reverse: 切换到设备后执行 `reverse:forward` / `reverse:list-forward` / `reverse:killforward`，设备端口 -> 电脑端口
+//!
+//! 规则中 `local` 始终是电脑一侧，`remote` 始终是设备一侧 (如 `tcp:8080`、`localabstract:scrcpy`)。
+//! 保存在 `Config.forward_rules` 中的规则会在设备连接时自动重新应用。
+
+use std::net::TcpStream;
+use anyhow::{Result, bail, Context};
+use serde::{Deserialize, Serialize};
+use crate::adb_host::{self, AdbHost};
+
+/// 转发方向
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardDirection {
+    /// 电脑 -> 设备 (adb forward)
+    Forward,
+    /// 设备 -> 电脑 (adb reverse)
+    Reverse,
+}
+
+/// 一条转发规则
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ForwardRule {
+    pub direction: ForwardDirection,
+    /// 电脑一侧，如 tcp:8080
+    pub local: String,
+    /// 设备一侧，如 tcp:8080、localabstract:scrcpy
+    pub remote: String,
+}
+
+impl ForwardRule {
+    /// 由 ADB 分配端口的一侧 (forward 为电脑端，reverse 为设备端)
+    fn port_side(&self) -> &str {
+        match self.direction {
+            ForwardDirection::Forward => &self.local,
+            ForwardDirection::Reverse => &self.remote,
+        }
+    }
+
+    /// 另一侧
+    fn target_side(&self) -> &str {
+        match self.direction {
+            ForwardDirection::Forward => &self.remote,
+            ForwardDirection::Reverse => &self.local,
+        }
+    }
+
+    /// `self` (来自 `list` 或 `add` 的返回值) 是否对应保存的规则 `saved`
+    ///
+    /// 方向和两端相同即为同一条规则；保存的是 `tcp:0` 时，实际端口是分配出来的 tcp 端口。
+    pub fn matches_saved(&self, saved: &ForwardRule) -> bool {
+        self.direction == saved.direction
+            && self.target_side() == saved.target_side()
+            && (self.port_side() == saved.port_side()
+                || (saved.port_side() == "tcp:0" && self.port_side().starts_with("tcp:")))
+    }
+}
+
+/// 从保存的规则中删除 `rule`，返回是否有规则被删除
+///
+/// 优先删除完全相同的规则，没有时再删除对应的 `tcp:0` 规则。
+pub fn remove_saved(rules: &mut Vec<ForwardRule>, rule: &ForwardRule) -> bool {
+    let before = rules.len();
+    if rules.contains(rule) {
+        rules.retain(|r| r != rule);
+    } else {
+        rules.retain(|r| !rule.matches_saved(r));
+    }
+    rules.len() != before
+}
+
+/// 检查端点格式 (`<类型>:<值>`，不能包含 `;`)
+fn validate_spec(spec: &str) -> Result<()> {
+    match spec.split_once(':') {
+        Some((kind, value)) if !kind.is_empty() && !value.is_empty() && !spec.contains(';') => Ok(()),
+        _ => bail!("无效的端点: {} (格式如 tcp:8080)", spec),
+    }
+}
+
+/// 解析 `list-forward` 的输出
+///
+/// forward 每行为 `<序列号> <电脑端> <设备端>`，只保留指定设备的规则；
+/// reverse 每行为 `<连接名> <设备端> <电脑端>`。
+pub fn parse_forward_list(text: &str, direction: ForwardDirection, serial: &str) -> Vec<ForwardRule> {
+    text.lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [owner, a, b] = parts[..] else {
+                return None;
+            };
+            let (local, remote) = match direction {
+                ForwardDirection::Forward if owner == serial => (a, b),
+                ForwardDirection::Forward => return None,
+                ForwardDirection::Reverse => (b, a),
+            };
+            Some(ForwardRule { direction, local: local.to_string(), remote: remote.to_string() })
+        })
+        .collect()
+}
+
+/// 设备端口转发管理
+pub struct AdbForwarder {
+    device_serial: String,
+    host: AdbHost,
+}
+
+impl AdbForwarder {
+    pub fn new(device_serial: String) -> Self {
+        Self { host: AdbHost::for_device(Some(&device_serial)), device_serial }
+    }
+
+    /// forward 使用 host-serial 服务
+    fn forward_service(&self, command: &str) -> Result<TcpStream> {
+        self.host.request(&format!("host-serial:{}:{}", self.device_serial, command))
+    }
+
+    /// reverse 需要先切换到设备再打开服务
+    fn reverse_service(&self, command: &str) -> Result<TcpStream> {
+        let mut stream = self
+            .host
+            .request(&format!("host:transport:{}", self.device_serial))
+            .context("设备连接失败或未授权")?;
+        adb_host::send_packet(&mut stream, &format!("reverse:{}", command))?;
+        adb_host::read_status(&mut stream)?;
+        Ok(stream)
+    }
+
+    /// 列出设备的 forward 和 reverse 规则
+    pub fn list(&self) -> Result<Vec<ForwardRule>> {
+        let mut stream = self.forward_service("list-forward")?;
+        let mut rules = parse_forward_list(
+            &adb_host::read_length_prefixed(&mut stream)?,
+            ForwardDirection::Forward,
+            &self.device_serial,
+        );
+
+        let mut stream = self.reverse_service("list-forward")?;
+        rules.extend(parse_forward_list(
+            &adb_host::read_length_prefixed(&mut stream)?,
+            ForwardDirection::Reverse,
+            &self.device_serial,
+        ));
+        Ok(rules)
+    }
+
+    /// 添加规则 (已存在时覆盖)，返回实际分配的端口
+    ///
+    /// `tcp:0` 会由 ADB 分配一个空闲端口，此时返回的规则中是实际端口。
+    pub fn add(&self, rule: &ForwardRule) -> Result<ForwardRule> {
+        validate_spec(&rule.local)?;
+        validate_spec(&rule.remote)?;
+
+        // 第一个 OKAY 表示请求已接受，第二个表示监听已建立
+        let (mut stream, port_spec) = match rule.direction {
+            ForwardDirection::Forward => (
+                self.forward_service(&format!("forward:{};{}", rule.local, rule.remote))?,
+                &rule.local,
+            ),
+            ForwardDirection::Reverse => (
+                self.reverse_service(&format!("forward:{};{}", rule.remote, rule.local))?,
+                &rule.remote,
+            ),
+        };
+        adb_host::read_status(&mut stream)?;
+
+        let mut added = rule.clone();
+        if port_spec == "tcp:0" {
+            let port = adb_host::read_length_prefixed(&mut stream)?;
+            let resolved = format!("tcp:{}", port.trim());
+            match rule.direction {
+                ForwardDirection::Forward => added.local = resolved,
+                ForwardDirection::Reverse => added.remote = resolved,
+            }
+        }
+        Ok(added)
+    }
+
+    /// 删除规则
+    pub fn remove(&self, rule: &ForwardRule) -> Result<()> {
+        let mut stream = match rule.direction {
+            ForwardDirection::Forward => self.forward_service(&format!("killforward:{}", rule.local))?,
+            ForwardDirection::Reverse => self.reverse_service(&format!("killforward:{}", rule.remote))?,
+        };
+        adb_host::read_status(&mut stream)
+    }
+
+    /// 应用一组规则，返回失败的规则及原因
+    pub fn apply(&self, rules: &[ForwardRule]) -> Vec<(ForwardRule, String)> {
+        rules
+            .iter()
+            .filter_map(|rule| self.add(rule).err().map(|e| (rule.clone(), format!("{:#}", e))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_forward_list() {
+        let forward = "emulator-5554 tcp:8080 tcp:80\n192.168.1.5:5555 tcp:27183 localabstract:scrcpy\n";
+        let rules = parse_forward_list(forward, ForwardDirection::Forward, "192.168.1.5:5555");
+        assert_eq!(rules, vec![ForwardRule {
+            direction: ForwardDirection::Forward,
+            local: "tcp:27183".into(),
+            remote: "localabstract:scrcpy".into(),
+        }]);
+
+        let reverse = "UsbFfs tcp:8081 tcp:3000\n";
+        let rules = parse_forward_list(reverse, ForwardDirection::Reverse, "R58M123");
+        assert_eq!(rules[0].local, "tcp:3000");
+        assert_eq!(rules[0].remote, "tcp:8081");
+
+        assert!(validate_spec("tcp:8080").is_ok());
+        assert!(validate_spec("tcp:8080;tcp:80").is_err());
+        assert!(validate_spec("8080").is_err());
+    }
+
+    #[test]
+    fn test_remove_saved() {
+        let rule = |direction, local: &str, remote: &str| ForwardRule { direction, local: local.into(), remote: remote.into() };
+        let auto = rule(ForwardDirection::Forward, "tcp:0", "localabstract:scrcpy");
+        let fixed = rule(ForwardDirection::Forward, "tcp:9000", "localabstract:scrcpy");
+        let reverse = rule(ForwardDirection::Reverse, "tcp:3000", "tcp:0");
+        let mut rules = vec![auto.clone(), fixed.clone(), reverse.clone()];
+
+        // 完全相同的规则只删除自己
+        assert!(remove_saved(&mut rules, &fixed));
+        assert_eq!(rules, vec![auto.clone(), reverse.clone()]);
+
+        // list-forward 返回的是分配后的端口，对应保存的 tcp:0 规则
+        let listed = rule(ForwardDirection::Forward, "tcp:41234", "localabstract:scrcpy");
+        assert!(remove_saved(&mut rules, &listed));
+        assert_eq!(rules, vec![reverse.clone()]);
+
+        assert!(!remove_saved(&mut rules, &rule(ForwardDirection::Reverse, "tcp:3001", "tcp:8081")));
+        assert!(remove_saved(&mut rules, &rule(ForwardDirection::Reverse, "tcp:3000", "tcp:8081")));
+        assert!(rules.is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+//! Scrcpy Launcher - ADB Host 服务客户端
+//!
+//! 直接与 ADB Server 通信 (host:devices-l / host:version / host:features / host:kill)，
+//! 取代解析 `adb devices` 命令行输出。
+//! 使用阻塞 socket 实现，可在同步命令和托盘回调中直接调用。
+//!
+//! 除本机 Server 外还可以配置多个命名的远程 Server (`Config.adb_servers`，相当于 `adb -H -P`)，
+//! 设备列表会记录每台设备来自哪个 Server，之后对该设备的操作都发往同一个 Server。
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use anyhow::{Result, bail, Context};
+use serde::{Deserialize, Serialize};
+use crate::adb_sync::parse_features;
+use crate::config::Config;
+
+/// ADB Server 默认端口
+pub const DEFAULT_SERVER_PORT: u16 = 5037;
+
+/// 本机 ADB Server 的名称
+pub const LOCAL_SERVER: &str = "local";
+
+/// 一个 ADB Server 端点
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AdbServer {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+}
+
+impl AdbServer {
+    /// 本机 ADB Server，端口遵循 `ANDROID_ADB_SERVER_PORT`
+    pub fn local() -> Self {
+        let port = std::env::var("ANDROID_ADB_SERVER_PORT")
+            .ok()
+            .and_then(|p| p.trim().parse().ok())
+            .unwrap_or(DEFAULT_SERVER_PORT);
+        Self { name: LOCAL_SERVER.to_string(), host: "127.0.0.1".to_string(), port }
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.name == LOCAL_SERVER
+    }
+
+    /// `host:port`
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// 传给 adb 命令行的参数
+    pub fn adb_args(&self) -> Vec<String> {
+        vec!["-H".to_string(), self.host.clone(), "-P".to_string(), self.port.to_string()]
+    }
+
+    /// 传给 scrcpy 的环境变量 (scrcpy 调用的 adb 会连接此 Server)，本机 Server 不需要
+    pub fn scrcpy_env(&self) -> Option<(&'static str, String)> {
+        (!self.is_local()).then(|| ("ADB_SERVER_SOCKET", format!("tcp:{}", self.addr())))
+    }
+
+    /// 传给 scrcpy 的参数：远程 Server 上建立的转发端口在远程主机上，需要连接到该主机
+    pub fn scrcpy_args(&self) -> Vec<String> {
+        if self.is_local() {
+            Vec::new()
+        } else {
+            vec!["--force-adb-forward".to_string(), format!("--tunnel-host={}", self.host)]
+        }
+    }
+}
+
+/// 全部 ADB Server：本机 + 配置中的远程 Server
+pub fn servers() -> Vec<AdbServer> {
+    let mut servers = vec![AdbServer::local()];
+    servers.extend(Config::load().adb_servers);
+    servers
+}
+
+/// 设备来源 (序列号 -> Server 名称)，由设备列表和设备跟踪更新
+static DEVICE_SOURCES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+fn device_sources() -> &'static Mutex<HashMap<String, String>> {
+    DEVICE_SOURCES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 记录某个 Server 当前的设备 (替换该 Server 之前的记录)
+pub fn record_devices(server: &str, devices: &[AdbDevice]) {
+    let mut sources = device_sources().lock().unwrap();
+    sources.retain(|_, s| s != server);
+    for device in devices {
+        sources.entry(device.serial.clone()).or_insert_with(|| server.to_string());
+    }
+}
+
+/// 设备所在的 Server (未知设备使用本机 Server)
+pub fn server_for(serial: Option<&str>) -> AdbServer {
+    let name = serial.and_then(|s| device_sources().lock().unwrap().get(s).cloned());
+    match name {
+        Some(name) if name != LOCAL_SERVER => Config::load()
+            .adb_servers
+            .into_iter()
+            .find(|s| s.name == name)
+            .unwrap_or_else(AdbServer::local),
+        _ => AdbServer::local(),
+    }
+}
+
+/// 设备连接状态 (对应 `adb devices` 的第二列)
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceState {
+    Device,
+    Offline,
+    Unauthorized,
+    Authorizing,
+    Connecting,
+    Recovery,
+    Rescue,
+    Sideload,
+    Bootloader,
+    Host,
+    NoPermissions,
+    Unknown,
+}
+
+impl DeviceState {
+    fn parse(s: &str) -> Self {
+        match s {
+            "device" => DeviceState::Device,
+            "offline" => DeviceState::Offline,
+            "unauthorized" => DeviceState::Unauthorized,
+            "authorizing" => DeviceState::Authorizing,
+            "connecting" => DeviceState::Connecting,
+            "recovery" => DeviceState::Recovery,
+            "rescue" => DeviceState::Rescue,
+            "sideload" => DeviceState::Sideload,
+            "bootloader" => DeviceState::Bootloader,
+            "host" => DeviceState::Host,
+            "no" => DeviceState::NoPermissions, // "no permissions (...)"
+            _ => DeviceState::Unknown,
+        }
+    }
+}
+
+/// 一台已连接的设备 (来自 host:devices-l)
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AdbDevice {
+    pub serial: String,
+    pub state: DeviceState,
+    pub product: Option<String>,
+    pub model: Option<String>,
+    pub device: Option<String>,
+    pub transport_id: Option<u64>,
+    /// 设备所在的 ADB Server 名称 (本机为 "local")
+    pub server: String,
+}
+
+impl AdbDevice {
+    /// 设备是否可用 (已授权且在线)
+    pub fn is_ready(&self) -> bool {
+        self.state == DeviceState::Device
+    }
+
+    /// 用于显示的名称：型号 (序列号)，远程 Server 上的设备附加 `@Server`
+    pub fn display_name(&self) -> String {
+        let name = match &self.model {
+            Some(model) => format!("{} ({})", model.replace('_', " "), self.serial),
+            None => self.serial.clone(),
+        };
+        if self.server == LOCAL_SERVER {
+            name
+        } else {
+            format!("{} @{}", name, self.server)
+        }
+    }
+}
+
+/// 解析 host:devices-l 的应答
+///
+/// 每行格式: `<serial> <state> [product:x] [model:y] [device:z] [transport_id:n]`
+pub fn parse_devices(text: &str) -> Vec<AdbDevice> {
+    text.lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            let serial = tokens.next()?.to_string();
+            let state = DeviceState::parse(tokens.next()?);
+
+            let mut device = AdbDevice {
+                serial,
+                state,
+                product: None,
+                model: None,
+                device: None,
+                transport_id: None,
+                server: LOCAL_SERVER.to_string(),
+            };
+            for token in tokens {
+                match token.split_once(':') {
+                    Some(("product", v)) => device.product = Some(v.to_string()),
+                    Some(("model", v)) => device.model = Some(v.to_string()),
+                    Some(("device", v)) => device.device = Some(v.to_string()),
+                    Some(("transport_id", v)) => device.transport_id = v.parse().ok(),
+                    _ => {}
+                }
+            }
+            Some(device)
+        })
+        .collect()
+}
+
+/// 发送 ADB 格式的数据包 (4字节长度 + 内容)
+pub(crate) fn send_packet(stream: &mut TcpStream, payload: &str) -> Result<()> {
+    stream.write_all(format!("{:04x}", payload.len()).as_bytes())?;
+    stream.write_all(payload.as_bytes())?;
+    Ok(())
+}
+
+/// 读取 4 位十六进制长度前缀的字符串
+pub(crate) fn read_length_prefixed(stream: &mut TcpStream) -> Result<String> {
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf)?;
+    let len = usize::from_str_radix(&String::from_utf8_lossy(&len_buf), 16)
+        .context("无效的应答长度")?;
+    let mut data = vec![0u8; len];
+    stream.read_exact(&mut data)?;
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+/// 读取 ADB 的 OKAY/FAIL 状态
+pub(crate) fn read_status(stream: &mut TcpStream) -> Result<()> {
+    let mut status = [0u8; 4];
+    stream.read_exact(&mut status)?;
+    match &status {
+        b"OKAY" => Ok(()),
+        b"FAIL" => {
+            let message = read_length_prefixed(stream).unwrap_or_default();
+            bail!("ADB Error: {}", message)
+        }
+        _ => bail!("未知的 ADB 应答: {}", String::from_utf8_lossy(&status)),
+    }
+}
+
+/// ADB Server 的 host 服务客户端
+#[derive(Clone, Debug)]
+pub struct AdbHost {
+    addr: String,
+    timeout: Duration,
+}
+
+impl Default for AdbHost {
+    /// 本机 ADB Server
+    fn default() -> Self {
+        Self::new(&AdbServer::local().addr())
+    }
+}
+
+impl AdbHost {
+    pub fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.to_string(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// 设备所在 ADB Server 的客户端
+    pub fn for_device(serial: Option<&str>) -> Self {
+        Self::new(&server_for(serial).addr())
+    }
+
+    /// ADB Server 地址
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// 连接 ADB Server
+    pub(crate) fn connect(&self) -> Result<TcpStream> {
+        let addr = self.addr
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut a| a.next())
+            .context("无效的 ADB Server 地址")?;
+        let stream = TcpStream::connect_timeout(&addr, Duration::from_secs(2))
+            .context("无法连接到 ADB Server")?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        Ok(stream)
+    }
+
+    /// 发送服务请求并确认 OKAY，返回连接供后续读取
+    pub(crate) fn request(&self, service: &str) -> Result<TcpStream> {
+        let mut stream = self.connect()?;
+        send_packet(&mut stream, service)?;
+        read_status(&mut stream)?;
+        Ok(stream)
+    }
+
+    /// 发送请求并读取一个长度前缀的应答
+    fn query(&self, service: &str) -> Result<String> {
+        let mut stream = self.request(service)?;
+        read_length_prefixed(&mut stream)
+    }
+
+    /// ADB Server 是否在运行
+    pub fn is_running(&self) -> bool {
+        self.version().is_ok()
+    }
+
+    /// ADB Server 的协议版本 (host:version)
+    pub fn version(&self) -> Result<u32> {
+        let version = self.query("host:version")?;
+        u32::from_str_radix(version.trim(), 16).context("无效的版本号")
+    }
+
+    /// 已连接设备列表 (host:devices-l)
+    pub fn devices(&self) -> Result<Vec<AdbDevice>> {
+        Ok(parse_devices(&self.query("host:devices-l")?))
+    }
+
+    /// 设备支持的特性 (host:features)，未指定序列号时要求只有一台设备
+    pub fn features(&self, serial: Option<&str>) -> Result<Vec<String>> {
+        let service = match serial {
+            Some(s) => format!("host-serial:{}:features", s),
+            None => "host:features".to_string(),
+        };
+        Ok(parse_features(&self.query(&service)?))
+    }
+
+    /// 关闭 ADB Server (host:kill)
+    pub fn kill(&self) -> Result<()> {
+        self.request("host:kill")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_devices() {
+        let text = "\
+emulator-5554          device product:sdk_gphone64_x86_64 model:sdk_gphone64_x86_64 device:emu64xa transport_id:1
+192.168.1.5:5555       unauthorized transport_id:3
+0123456789ABCDEF       no permissions (missing udev rules? user is in the plugdev group); see [http://developer.android.com/tools/device.html] usb:1-1 transport_id:4
+";
+        let devices = parse_devices(text);
+        assert_eq!(devices.len(), 3);
+
+        assert_eq!(devices[0].serial, "emulator-5554");
+        assert!(devices[0].is_ready());
+        assert_eq!(devices[0].model.as_deref(), Some("sdk_gphone64_x86_64"));
+        assert_eq!(devices[0].transport_id, Some(1));
+
+        assert_eq!(devices[1].state, DeviceState::Unauthorized);
+        assert!(!devices[1].is_ready());
+        assert_eq!(devices[1].model, None);
+        assert_eq!(devices[1].transport_id, Some(3));
+
+        assert_eq!(devices[2].state, DeviceState::NoPermissions);
+        assert_eq!(devices[2].transport_id, Some(4));
+    }
+
+    #[test]
+    fn test_adb_server_args() {
+        let lab = AdbServer { name: "lab".into(), host: "10.0.0.8".into(), port: 5037 };
+        assert_eq!(lab.adb_args(), vec!["-H", "10.0.0.8", "-P", "5037"]);
+        assert_eq!(lab.scrcpy_env(), Some(("ADB_SERVER_SOCKET", "tcp:10.0.0.8:5037".to_string())));
+        assert_eq!(lab.scrcpy_args(), vec!["--force-adb-forward", "--tunnel-host=10.0.0.8"]);
+
+        let local = AdbServer::local();
+        assert!(local.scrcpy_env().is_none() && local.scrcpy_args().is_empty());
+    }
+}
@@ -0,0 +1,398 @@
+//! Scrcpy Launcher - APK 安装模块
+//!
+//! 通过 ADB Server 把 APK 直接流式传给设备上的包管理器：
+//! - 设备支持 `cmd` 特性 (Android 7+) 时使用 `exec:cmd package install -S <size>`，无需先推送到设备
+//! - 旧设备回退为推送到 /data/local/tmp 后执行 `pm install`
+//!
+//! 分包压缩包 (.apks/.xapk/.apkm) 按设备配置选出分包后，
+//! 通过 `install-create` / `install-write` / `install-commit` 会话一次安装。
+//!
+//! 包管理器的输出 (`Success` / `Failure [INSTALL_FAILED_*]`) 会被解析为 `InstallError`。
+
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use crate::adb_shell::{shell_quote, AdbShell};
+use crate::apk_bundle::{self, DeviceSpec, SplitApk};
+use crate::adb_sync::{AdbPusher, ProgressCallback};
+
+/// 分包安装的临时目录序号，同时进行的安装各用一个目录
+static NEXT_INSTALL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 旧设备回退安装时 APK 的临时目录
+const TEMP_DIR: &str = "/data/local/tmp";
+
+/// 安装选项
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct InstallOptions {
+    /// 覆盖安装已存在的应用 (-r)
+    pub replace: bool,
+    /// 允许降级安装 (-d)
+    pub downgrade: bool,
+    /// 授予清单中声明的全部运行时权限 (-g)
+    pub grant_permissions: bool,
+}
+
+impl InstallOptions {
+    /// 对应的 `pm install` 参数
+    pub(crate) fn args(&self) -> String {
+        let mut args = Vec::new();
+        if self.replace {
+            args.push("-r");
+        }
+        if self.downgrade {
+            args.push("-d");
+        }
+        if self.grant_permissions {
+            args.push("-g");
+        }
+        args.join(" ")
+    }
+}
+
+/// 安装方式
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallMethod {
+    /// exec:cmd package install 流式安装
+    Streamed,
+    /// 推送到设备后 pm install
+    Pushed,
+    /// 分包压缩包，install-create 会话安装
+    Session,
+}
+
+/// 安装失败的类型
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallErrorKind {
+    AlreadyExists,
+    VersionDowngrade,
+    UpdateIncompatible,
+    InsufficientStorage,
+    NoMatchingAbis,
+    OlderSdk,
+    InvalidApk,
+    TestOnly,
+    Aborted,
+    Other,
+}
+
+/// 包管理器返回的安装错误
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct InstallError {
+    pub kind: InstallErrorKind,
+    /// 原始错误代码，如 INSTALL_FAILED_VERSION_DOWNGRADE
+    pub code: String,
+    pub message: String,
+}
+
+impl InstallError {
+    fn new(code: &str, message: &str) -> Self {
+        let kind = match code {
+            "INSTALL_FAILED_ALREADY_EXISTS" => InstallErrorKind::AlreadyExists,
+            "INSTALL_FAILED_VERSION_DOWNGRADE" => InstallErrorKind::VersionDowngrade,
+            "INSTALL_FAILED_UPDATE_INCOMPATIBLE" | "INSTALL_FAILED_SHARED_USER_INCOMPATIBLE" => InstallErrorKind::UpdateIncompatible,
+            "INSTALL_FAILED_INSUFFICIENT_STORAGE" => InstallErrorKind::InsufficientStorage,
+            "INSTALL_FAILED_NO_MATCHING_ABIS" => InstallErrorKind::NoMatchingAbis,
+            "INSTALL_FAILED_OLDER_SDK" => InstallErrorKind::OlderSdk,
+            "INSTALL_FAILED_TEST_ONLY" => InstallErrorKind::TestOnly,
+            "INSTALL_FAILED_ABORTED" | "INSTALL_FAILED_USER_RESTRICTED" => InstallErrorKind::Aborted,
+            c if c.starts_with("INSTALL_PARSE_FAILED") || c == "INSTALL_FAILED_INVALID_APK" => InstallErrorKind::InvalidApk,
+            _ => InstallErrorKind::Other,
+        };
+        Self { kind, code: code.to_string(), message: message.to_string() }
+    }
+
+    /// 面向用户的说明
+    fn hint(&self) -> Option<&'static str> {
+        match self.kind {
+            InstallErrorKind::AlreadyExists => Some("应用已存在，请勾选覆盖安装"),
+            InstallErrorKind::VersionDowngrade => Some("设备上已安装更高版本，请勾选允许降级"),
+            InstallErrorKind::UpdateIncompatible => Some("签名与已安装的版本不一致，请先卸载旧版本"),
+            InstallErrorKind::InsufficientStorage => Some("设备存储空间不足"),
+            InstallErrorKind::NoMatchingAbis => Some("APK 不支持该设备的 CPU 架构"),
+            InstallErrorKind::OlderSdk => Some("设备的 Android 版本过低"),
+            InstallErrorKind::InvalidApk => Some("APK 文件无效或已损坏"),
+            InstallErrorKind::TestOnly => Some("测试版 APK 需要允许测试包安装"),
+            InstallErrorKind::Aborted => Some("安装被设备拒绝，请在手机上允许 USB 安装"),
+            InstallErrorKind::Other => None,
+        }
+    }
+}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hint() {
+            Some(hint) => write!(f, "{} [{}]", hint, self.code),
+            None if self.message.is_empty() => write!(f, "{}", self.code),
+            None => write!(f, "{}: {}", self.code, self.message),
+        }
+    }
+}
+
+impl std::error::Error for InstallError {}
+
+/// 解析包管理器的输出
+///
+/// 成功时输出包含 `Success`，失败时为 `Failure [CODE: message]`。
+pub fn parse_install_output(output: &str) -> std::result::Result<(), InstallError> {
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("Success") {
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix("Failure [") {
+            let inner = rest.strip_suffix(']').unwrap_or(rest);
+            let (code, message) = inner.split_once(':').unwrap_or((inner, ""));
+            return Err(InstallError::new(code.trim(), message.trim()));
+        }
+    }
+
+    let message = output.trim();
+    Err(InstallError::new("UNKNOWN", if message.is_empty() { "包管理器无输出" } else { message }))
+}
+
+/// 安装结果 (与 CommandResult 一样带 success/message，失败时附带错误类型)
+#[derive(Serialize, Clone, Debug)]
+pub struct InstallReport {
+    pub success: bool,
+    pub message: String,
+    pub method: Option<InstallMethod>,
+    pub error: Option<InstallError>,
+    /// 分包安装时选中的分包
+    pub splits: Vec<SplitApk>,
+}
+
+/// 从 `install-create` 的输出中取出会话 ID
+///
+/// 输出格式: `Success: created install session [1234]`
+fn parse_session_id(output: &str) -> std::result::Result<u32, InstallError> {
+    output
+        .lines()
+        .find(|l| l.contains("install session"))
+        .and_then(|l| l.rsplit('[').next())
+        .and_then(|id| id.trim().trim_end_matches(']').parse().ok())
+        .ok_or_else(|| parse_install_output(output).err().unwrap_or_else(|| InstallError::new("UNKNOWN", output.trim())))
+}
+
+/// APK 安装器
+pub struct AdbInstaller {
+    device_serial: Option<String>,
+    pusher: AdbPusher,
+}
+
+impl AdbInstaller {
+    pub fn new(serial: Option<String>) -> Self {
+        Self {
+            pusher: AdbPusher::new(serial.clone()),
+            device_serial: serial,
+        }
+    }
+
+    /// 安装 APK，进度回调为 (已发送字节, 总字节)
+    ///
+    /// 包管理器返回失败时错误可通过 `downcast_ref::<InstallError>()` 取得。
+    pub async fn install(&self, apk_path: &str, options: &InstallOptions, callback: Option<ProgressCallback>) -> Result<InstallMethod> {
+        let size = tokio::fs::metadata(apk_path).await
+            .with_context(|| format!("无法读取 APK: {}", apk_path))?
+            .len();
+
+        if self.pusher.has_feature("cmd").await {
+            let command = format!("cmd package install {} -S {}", options.args(), size);
+            let output = self.exec_with_input(&command, apk_path, &mut |sent| {
+                if let Some(cb) = &callback {
+                    cb(sent, size);
+                }
+            }).await?;
+            parse_install_output(&output)?;
+            Ok(InstallMethod::Streamed)
+        } else {
+            self.install_pushed(apk_path, options, callback).await?;
+            Ok(InstallMethod::Pushed)
+        }
+    }
+
+    /// 安装分包压缩包，返回选中的分包
+    ///
+    /// 设备不支持 `cmd` 时会话命令改用 `pm` 执行 (Android 5+)，APK 同样通过标准输入写入。
+    pub async fn install_bundle(&self, bundle_path: &str, options: &InstallOptions, callback: Option<ProgressCallback>) -> Result<Vec<SplitApk>> {
+        // 1. 读取设备配置并选出分包
+        let spec = DeviceSpec::parse(&self.shell(DeviceSpec::GETPROP_COMMAND.to_string()).await?.stdout);
+        let path = bundle_path.to_string();
+        let splits = tokio::task::spawn_blocking(move || apk_bundle::list_splits(&path)).await??;
+        let selected = apk_bundle::select_splits(&splits, &spec)?;
+
+        // 2. 解压到临时目录
+        let temp_dir = std::env::temp_dir().join(format!(
+            "scrcpy-launcher-install-{}-{}",
+            std::process::id(),
+            NEXT_INSTALL_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let (path, chosen, dir) = (bundle_path.to_string(), selected.clone(), temp_dir.clone());
+        let files = tokio::task::spawn_blocking(move || apk_bundle::extract(&path, &chosen, &dir)).await?;
+
+        let result = match files {
+            Ok(files) => self.install_session(&files, options, callback).await,
+            Err(e) => Err(e),
+        };
+        let _ = tokio::fs::remove_dir_all(&temp_dir).await;
+        result.map(|_| selected)
+    }
+
+    /// 创建安装会话、逐个写入分包并提交，失败时放弃会话
+    async fn install_session(
+        &self,
+        files: &[std::path::PathBuf],
+        options: &InstallOptions,
+        callback: Option<ProgressCallback>,
+    ) -> Result<()> {
+        let pm = if self.pusher.has_feature("cmd").await { "cmd package" } else { "pm" };
+        let mut sizes = Vec::new();
+        for file in files {
+            sizes.push(tokio::fs::metadata(file).await?.len());
+        }
+        let total: u64 = sizes.iter().sum();
+
+        let output = self.exec(&format!("{} install-create {} -S {}", pm, options.args(), total)).await?;
+        let session = parse_session_id(&output)?;
+
+        let mut written = 0u64;
+        let result: Result<()> = async {
+            // 会话内的文件名用序号，分包名来自压缩包，不能直接拼进 sh -c 执行的命令
+            for (index, (file, size)) in files.iter().zip(&sizes).enumerate() {
+                let command = format!("{} install-write -S {} {} split{}.apk -", pm, size, session, index);
+                let output = self.exec_with_input(&command, &file.to_string_lossy(), &mut |sent| {
+                    if let Some(cb) = &callback {
+                        cb(written + sent, total);
+                    }
+                }).await?;
+                parse_install_output(&output)?;
+                written += size;
+            }
+            parse_install_output(&self.exec(&format!("{} install-commit {}", pm, session)).await?)?;
+            Ok(())
+        }.await;
+
+        if result.is_err() {
+            let _ = self.exec(&format!("{} install-abandon {}", pm, session)).await;
+        }
+        result
+    }
+
+    /// 执行 `exec:` 命令并返回输出
+    async fn exec(&self, command: &str) -> Result<String> {
+        let mut stream = self.pusher.open_service(&format!("exec:{} 2>&1", command)).await?;
+        let mut output = Vec::new();
+        stream.read_to_end(&mut output).await?;
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    /// 旧设备：推送到临时目录后执行 pm install，结束后删除临时文件
+    async fn install_pushed(&self, apk_path: &str, options: &InstallOptions, callback: Option<ProgressCallback>) -> Result<()> {
+        let file_name = Path::new(apk_path)
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or("install.apk");
+        let remote = format!("{}/{}", TEMP_DIR, file_name);
+        self.pusher.push(apk_path, &remote, None, callback).await?;
+
+        let output = self.shell(format!("pm install {} {}", options.args(), shell_quote(&remote))).await;
+        let _ = self.shell(format!("rm -f {}", shell_quote(&remote))).await;
+
+        let output = output?;
+        parse_install_output(&format!("{}\n{}", output.stdout, output.stderr))?;
+        Ok(())
+    }
+
+    /// 在阻塞线程中执行 shell 命令
+    async fn shell(&self, command: String) -> Result<crate::adb_shell::ShellOutput> {
+        let shell = AdbShell::new(self.device_serial.clone());
+        tokio::task::spawn_blocking(move || shell.run(&command)).await?
+    }
+
+    /// 执行 `exec:` 命令并把本地文件作为其标准输入，返回命令输出
+    ///
+    /// 命令提前失败时 (如包管理器拒绝安装) 写入会中断，此时仍会读取输出以获得失败原因。
+    pub(crate) async fn exec_with_input(
+        &self,
+        command: &str,
+        local_path: &str,
+        on_sent: &mut (dyn FnMut(u64) + Send),
+    ) -> Result<String> {
+        let mut file = tokio::fs::File::open(local_path).await
+            .with_context(|| format!("无法打开文件: {}", local_path))?;
+        let mut stream = self.pusher.open_service(&format!("exec:{} 2>&1", command)).await?;
+
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut sent = 0u64;
+        let write_result: Result<()> = async {
+            loop {
+                let n = file.read(&mut buffer).await?;
+                if n == 0 {
+                    break;
+                }
+                stream.write_all(&buffer[..n]).await?;
+                sent += n as u64;
+                on_sent(sent);
+            }
+            Ok(())
+        }.await;
+
+        let mut output = Vec::new();
+        let read_result = stream.read_to_end(&mut output).await;
+        let output = String::from_utf8_lossy(&output).to_string();
+
+        if let Err(e) = write_result {
+            // 有输出说明命令已给出结果 (通常是 Failure)，交给调用方解析
+            if output.trim().is_empty() {
+                return Err(e.context("发送数据失败"));
+            }
+        }
+        read_result?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_install_output() {
+        assert!(parse_install_output("Performing Streamed Install\nSuccess\n").is_ok());
+
+        let err = parse_install_output("Failure [INSTALL_FAILED_VERSION_DOWNGRADE: Downgrade detected: Update version code 1 is older than current 2]\n").unwrap_err();
+        assert_eq!(err.kind, InstallErrorKind::VersionDowngrade);
+        assert_eq!(err.code, "INSTALL_FAILED_VERSION_DOWNGRADE");
+        assert!(err.message.starts_with("Downgrade detected"));
+
+        let err = parse_install_output("Failure [INSTALL_PARSE_FAILED_NOT_APK: Failed to parse /data/app/vmdl.tmp/base.apk]").unwrap_err();
+        assert_eq!(err.kind, InstallErrorKind::InvalidApk);
+
+        let err = parse_install_output("Failure [INSTALL_FAILED_ALREADY_EXISTS]").unwrap_err();
+        assert_eq!(err.kind, InstallErrorKind::AlreadyExists);
+        assert_eq!(err.message, "");
+
+        let err = parse_install_output("Error: Unknown option: -x\n").unwrap_err();
+        assert_eq!(err.kind, InstallErrorKind::Other);
+        assert_eq!(err.message, "Error: Unknown option: -x");
+    }
+
+    #[test]
+    fn test_parse_session_id() {
+        assert_eq!(parse_session_id("Success: created install session [1234567]\n"), Ok(1234567));
+        let err = parse_session_id("Failure [INSTALL_FAILED_INSUFFICIENT_STORAGE]").unwrap_err();
+        assert_eq!(err.kind, InstallErrorKind::InsufficientStorage);
+    }
+
+    #[test]
+    fn test_install_options_args() {
+        assert_eq!(InstallOptions::default().args(), "");
+        let options = InstallOptions { replace: true, downgrade: true, grant_permissions: true };
+        assert_eq!(options.args(), "-r -d -g");
+    }
+}
@@ -0,0 +1,313 @@
+//! Scrcpy Launcher - ADB 密钥模块
+//!
+//! 直连设备 (`adb_transport`) 时用于 AUTH 认证的 RSA 密钥：
+//! - 私钥 `adbkey` 为 PKCS#8 PEM 格式，与 adb 使用的 `~/.android/adbkey` 相同
+//! - 公钥 `adbkey.pub` 为 Android 公钥格式 (base64 编码的 RSAPublicKey 结构 + 空格 + 注释)，
+//!   设备未信任时发送给设备等待用户确认
+//! - 指纹为公钥结构的 MD5，与手机授权对话框中显示的一致
+//!
+//! 团队密钥保存在 `keys/<团队>/adbkey`，选中后 (`Config.adb_key_team`) 直连设备使用它，
+//! 启动的 adb/scrcpy 进程也会通过 `ADB_VENDOR_KEYS` 使用它，
+//! 因此测试机只需信任一次随启动器分发的团队密钥。
+
+use std::path::{Path, PathBuf};
+use anyhow::{Result, bail, Context};
+use base64::prelude::*;
+use md5::{Digest, Md5};
+use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use serde::Serialize;
+use sha1::Sha1;
+use crate::config::Config;
+
+/// adb 认证令牌长度 (与 SHA-1 摘要相同)
+pub const TOKEN_SIZE: usize = 20;
+
+/// adbd 只接受 2048 位密钥
+const KEY_BITS: usize = 2048;
+
+/// 团队密钥目录
+const KEYS_DIR: &str = "keys";
+
+/// 私钥文件名
+const KEY_FILE: &str = "adbkey";
+
+/// ADB RSA 密钥
+pub struct AdbKey {
+    key: RsaPrivateKey,
+    /// Android 格式的公钥 (adbkey.pub 的内容)
+    public_key: String,
+}
+
+impl AdbKey {
+    /// adb 默认的私钥路径 (`$ANDROID_USER_HOME/adbkey` 或 `~/.android/adbkey`)
+    pub fn default_path() -> PathBuf {
+        std::env::var_os("ANDROID_USER_HOME")
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|h| h.join(".android")))
+            .unwrap_or_default()
+            .join(KEY_FILE)
+    }
+
+    /// 生成新的 2048 位密钥
+    pub fn generate(comment: &str) -> Result<Self> {
+        let key = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, KEY_BITS).context("生成密钥失败")?;
+        let public_key = android_public_key(&key.to_public_key(), comment);
+        Ok(Self { key, public_key })
+    }
+
+    /// 读取私钥，同目录下存在 `<私钥>.pub` 时使用其中的公钥 (保留原注释)
+    pub fn load(path: &Path) -> Result<Self> {
+        let pem = std::fs::read_to_string(path)
+            .with_context(|| format!("无法读取 ADB 私钥: {}", path.display()))?;
+        let key = RsaPrivateKey::from_pkcs8_pem(&pem).context("无效的 ADB 私钥")?;
+
+        let public_key = std::fs::read_to_string(pub_path(path))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| android_public_key(&key.to_public_key(), &default_comment()));
+
+        Ok(Self { key, public_key })
+    }
+
+    /// 读取 adb 默认的密钥
+    pub fn load_default() -> Result<Self> {
+        Self::load(&Self::default_path())
+    }
+
+    /// 读取当前使用的密钥：选中的团队密钥，否则为 adb 默认密钥
+    ///
+    /// 默认密钥不存在时与 adb 一样自动生成。
+    pub fn load_active() -> Result<Self> {
+        if let Some(team) = Config::load().adb_key_team {
+            return Self::load(&team_key_path(&team)?);
+        }
+
+        let path = Self::default_path();
+        if !path.exists() {
+            let key = Self::generate(&default_comment())?;
+            key.save(&path)?;
+            return Ok(key);
+        }
+        Self::load(&path)
+    }
+
+    /// 由内存中的私钥创建
+    pub fn from_private_key(key: RsaPrivateKey) -> Self {
+        let public_key = android_public_key(&key.to_public_key(), &default_comment());
+        Self { key, public_key }
+    }
+
+    /// 保存私钥 (PKCS#8 PEM) 和 `<私钥>.pub`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let pem = self.key.to_pkcs8_pem(LineEnding::LF).context("编码私钥失败")?;
+        std::fs::write(path, pem.as_bytes())
+            .with_context(|| format!("无法写入: {}", path.display()))?;
+        std::fs::write(pub_path(path), format!("{}\n", self.public_key))?;
+        Ok(())
+    }
+
+    /// 对设备发来的 20 字节令牌签名
+    ///
+    /// adbd 把令牌当作 SHA-1 摘要校验 (RSA PKCS#1 v1.5)，因此不再做哈希。
+    pub fn sign(&self, token: &[u8]) -> Result<Vec<u8>> {
+        self.key
+            .sign(Pkcs1v15Sign::new::<Sha1>(), token)
+            .context("签名认证令牌失败")
+    }
+
+    /// Android 格式的公钥
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// 手机授权对话框中显示的指纹 (公钥结构的 MD5，如 `AB:CD:...`)
+    pub fn fingerprint(&self) -> String {
+        let encoded = self.public_key.split_whitespace().next().unwrap_or("");
+        let blob = BASE64_STANDARD.decode(encoded).unwrap_or_default();
+        Md5::digest(&blob)
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
+/// `<私钥>.pub` 路径
+fn pub_path(path: &Path) -> PathBuf {
+    let mut pub_path = path.as_os_str().to_owned();
+    pub_path.push(".pub");
+    PathBuf::from(pub_path)
+}
+
+/// 公钥注释，与 adb 一样为 `用户@主机`
+fn default_comment() -> String {
+    let user = std::env::var("USERNAME").or_else(|_| std::env::var("USER")).unwrap_or_else(|_| "unknown".into());
+    let host = std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")).unwrap_or_else(|_| "unknown".into());
+    format!("{}@{}", user, host)
+}
+
+/// 编码 Android 公钥结构 (均为小端 u32)：
+/// 模数字数、-1/n[0] mod 2^32、模数 n、R^2 mod n (R = 2^模数位数)、公钥指数
+fn android_public_key_blob(key: &RsaPublicKey) -> Vec<u8> {
+    let size = key.size();
+    let le_bytes = |value: &BigUint| {
+        let mut bytes = value.to_bytes_le();
+        bytes.resize(size, 0);
+        bytes
+    };
+
+    let n = le_bytes(key.n());
+    let n0 = u32::from_le_bytes([n[0], n[1], n[2], n[3]]);
+    // 牛顿迭代求 n0 在 mod 2^32 下的逆元 (n 为奇数)
+    let mut inverse = 1u32;
+    for _ in 0..5 {
+        inverse = inverse.wrapping_mul(2u32.wrapping_sub(n0.wrapping_mul(inverse)));
+    }
+    let rr = (BigUint::from(1u32) << (size * 16)) % key.n();
+    let exponent = key.e().to_bytes_le().iter().rev().fold(0u32, |e, &b| (e << 8) | b as u32);
+
+    let mut blob = Vec::with_capacity(12 + size * 2);
+    blob.extend_from_slice(&((size / 4) as u32).to_le_bytes());
+    blob.extend_from_slice(&inverse.wrapping_neg().to_le_bytes());
+    blob.extend_from_slice(&n);
+    blob.extend_from_slice(&le_bytes(&rr));
+    blob.extend_from_slice(&exponent.to_le_bytes());
+    blob
+}
+
+/// adbkey.pub 格式：base64(公钥结构) + 空格 + 注释
+fn android_public_key(key: &RsaPublicKey, comment: &str) -> String {
+    format!("{} {}", BASE64_STANDARD.encode(android_public_key_blob(key)), comment)
+}
+
+/// 团队密钥的路径
+pub fn team_key_path(team: &str) -> Result<PathBuf> {
+    let valid = !team.is_empty()
+        && team != "."
+        && team != ".."
+        && !team.contains(['/', '\\', ':']);
+    if !valid {
+        bail!("无效的团队名: {}", team);
+    }
+    Ok(PathBuf::from(KEYS_DIR).join(team).join(KEY_FILE))
+}
+
+/// 密钥信息 (供前端展示)
+#[derive(Serialize, Clone, Debug)]
+pub struct AdbKeyInfo {
+    /// 团队名，None 表示 adb 默认密钥
+    pub team: Option<String>,
+    pub path: String,
+    pub fingerprint: String,
+    pub public_key: String,
+    /// 直连设备和启动的进程是否使用此密钥
+    pub active: bool,
+}
+
+impl AdbKeyInfo {
+    fn new(team: Option<String>, path: &Path, key: &AdbKey, active: bool) -> Self {
+        Self {
+            team,
+            path: path.to_string_lossy().to_string(),
+            fingerprint: key.fingerprint(),
+            public_key: key.public_key().to_string(),
+            active,
+        }
+    }
+}
+
+/// 列出 adb 默认密钥和全部团队密钥
+pub fn list_keys() -> Vec<AdbKeyInfo> {
+    let active_team = Config::load().adb_key_team;
+    let mut keys = Vec::new();
+
+    let default_path = AdbKey::default_path();
+    if let Ok(key) = AdbKey::load(&default_path) {
+        keys.push(AdbKeyInfo::new(None, &default_path, &key, active_team.is_none()));
+    }
+
+    let mut teams: Vec<String> = std::fs::read_dir(KEYS_DIR)
+        .map(|dir| dir.flatten().filter_map(|e| e.file_name().into_string().ok()).collect())
+        .unwrap_or_default();
+    teams.sort();
+    for team in teams {
+        let Ok(path) = team_key_path(&team) else { continue };
+        if let Ok(key) = AdbKey::load(&path) {
+            let active = active_team.as_deref() == Some(team.as_str());
+            keys.push(AdbKeyInfo::new(Some(team), &path, &key, active));
+        }
+    }
+    keys
+}
+
+/// 生成团队密钥 (已存在时报错)
+pub fn generate_team_key(team: &str) -> Result<AdbKeyInfo> {
+    let path = team_key_path(team)?;
+    if path.exists() {
+        bail!("团队密钥已存在: {}", team);
+    }
+    let key = AdbKey::generate(&format!("{}@scrcpy-launcher", team))?;
+    key.save(&path)?;
+    Ok(AdbKeyInfo::new(Some(team.to_string()), &path, &key, false))
+}
+
+/// 导入已有的私钥作为团队密钥 (覆盖同名密钥)
+pub fn import_team_key(team: &str, private_key_path: &str) -> Result<AdbKeyInfo> {
+    let key = AdbKey::load(Path::new(private_key_path))?;
+    let path = team_key_path(team)?;
+    key.save(&path)?;
+    Ok(AdbKeyInfo::new(Some(team.to_string()), &path, &key, false))
+}
+
+/// 导出密钥 (团队名为 None 时导出 adb 默认密钥) 到目录，写入 adbkey 和 adbkey.pub
+pub fn export_key(team: Option<&str>, dir: &str) -> Result<PathBuf> {
+    let source = match team {
+        Some(team) => team_key_path(team)?,
+        None => AdbKey::default_path(),
+    };
+    let target = Path::new(dir).join(KEY_FILE);
+    AdbKey::load(&source)?.save(&target)?;
+    Ok(target)
+}
+
+/// 启动 adb/scrcpy 时的 `ADB_VENDOR_KEYS`：选中的团队密钥路径
+pub fn vendor_keys() -> Option<PathBuf> {
+    let team = Config::load().adb_key_team?;
+    let path = team_key_path(&team).ok()?;
+    std::fs::canonicalize(path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_android_public_key() {
+        let private = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 1024).unwrap();
+        let public = private.to_public_key();
+        let blob = android_public_key_blob(&public);
+        assert_eq!(blob.len(), 4 + 4 + 128 + 128 + 4);
+
+        let word = |i: usize| u32::from_le_bytes(blob[i..i + 4].try_into().unwrap());
+        assert_eq!(word(0), 32);
+        // n0inv * n[0] ≡ -1 (mod 2^32)
+        assert_eq!(word(4).wrapping_mul(word(8)), u32::MAX);
+        assert_eq!(BigUint::from_bytes_le(&blob[8..136]), *public.n());
+        assert_eq!(word(264), 65537);
+
+        let key = AdbKey::from_private_key(private);
+        assert!(key.public_key().ends_with(&format!(" {}", default_comment())));
+        let fingerprint = key.fingerprint();
+        assert_eq!(fingerprint.len(), 16 * 3 - 1);
+        assert!(fingerprint.split(':').all(|b| b.len() == 2));
+
+        assert!(team_key_path("qa-lab").is_ok());
+        assert!(team_key_path("../etc").is_err());
+    }
+}
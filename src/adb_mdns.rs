@@ -0,0 +1,193 @@
+//! Scrcpy Launcher - mDNS 设备发现模块
+//!
+//! Android 11+ 的无线调试使用随机端口，端口扫描找不到，
+//! 但设备会通过 mDNS/DNS-SD 广播两类服务：
+//! - `_adb-tls-connect._tcp`: 已配对设备的连接端口
+//! - `_adb-tls-pairing._tcp`: 打开"使用配对码配对"界面时的配对端口
+
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::{Result, Context};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+
+/// 连接服务类型
+pub const CONNECT_SERVICE: &str = "_adb-tls-connect._tcp.local.";
+
+/// 配对服务类型
+pub const PAIRING_SERVICE: &str = "_adb-tls-pairing._tcp.local.";
+
+/// 服务类别
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MdnsServiceKind {
+    /// 可直接连接
+    Connect,
+    /// 需要配对码配对
+    Pairing,
+}
+
+impl MdnsServiceKind {
+    fn service_type(self) -> &'static str {
+        match self {
+            Self::Connect => CONNECT_SERVICE,
+            Self::Pairing => PAIRING_SERVICE,
+        }
+    }
+}
+
+/// 发现的一个无线调试服务
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct MdnsService {
+    pub kind: MdnsServiceKind,
+    /// 服务实例名，如 adb-R58M123-AbCdEf
+    pub name: String,
+    /// mDNS 主机名，如 Android-2.local.
+    pub host: String,
+    pub ip: String,
+    pub port: u16,
+}
+
+impl MdnsService {
+    /// `ip:port`，可直接用于 adb connect / adb pair
+    pub fn addr(&self) -> String {
+        match self.ip.parse::<IpAddr>() {
+            Ok(IpAddr::V6(_)) => format!("[{}]:{}", self.ip, self.port),
+            _ => format!("{}:{}", self.ip, self.port),
+        }
+    }
+}
+
+/// 从完整服务名中去掉服务类型，得到实例名
+fn instance_name(fullname: &str, service_type: &str) -> String {
+    fullname
+        .strip_suffix(service_type)
+        .map(|name| name.trim_end_matches('.'))
+        .unwrap_or(fullname)
+        .to_string()
+}
+
+/// 转换解析结果 (优先使用 IPv4 地址)
+fn to_service(kind: MdnsServiceKind, info: &ServiceInfo) -> Option<MdnsService> {
+    let addresses = info.get_addresses();
+    let ip = addresses
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or_else(|| addresses.iter().next())?;
+    Some(MdnsService {
+        kind,
+        name: instance_name(info.get_fullname(), kind.service_type()),
+        host: info.get_hostname().to_string(),
+        ip: ip.to_string(),
+        port: info.get_port(),
+    })
+}
+
+/// 浏览无线调试服务，每次服务列表变化时调用 `on_change` (第二个参数为刚解析出的服务)
+///
+/// `on_change` 返回 `true` 时立即结束；`cancel` 被置位或超时后也会结束。
+/// 阻塞调用，在 async 上下文中请放到 `spawn_blocking` 中执行。
+fn run(
+    timeout: Duration,
+    cancel: &AtomicBool,
+    mut on_change: impl FnMut(&[MdnsService], Option<&MdnsService>) -> bool,
+) -> Result<Vec<MdnsService>> {
+    let daemon = ServiceDaemon::new().context("启动 mDNS 失败")?;
+    let receivers = [MdnsServiceKind::Connect, MdnsServiceKind::Pairing]
+        .into_iter()
+        .map(|kind| Ok((kind, daemon.browse(kind.service_type())?)))
+        .collect::<Result<Vec<_>, mdns_sd::Error>>()
+        .context("mDNS 浏览失败")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut services: Vec<MdnsService> = Vec::new();
+    'outer: while Instant::now() < deadline && !cancel.load(Ordering::Relaxed) {
+        let mut idle = true;
+        for (kind, receiver) in &receivers {
+            while let Ok(event) = receiver.try_recv() {
+                idle = false;
+                let resolved = match event {
+                    ServiceEvent::ServiceResolved(info) => {
+                        let Some(service) = to_service(*kind, &info) else {
+                            continue;
+                        };
+                        services.retain(|s| !(s.kind == service.kind && s.name == service.name));
+                        services.push(service);
+                        services.last()
+                    }
+                    ServiceEvent::ServiceRemoved(ty, fullname) => {
+                        let name = instance_name(&fullname, &ty);
+                        services.retain(|s| !(s.kind == *kind && s.name == name));
+                        None
+                    }
+                    _ => continue,
+                };
+                if on_change(&services, resolved) {
+                    break 'outer;
+                }
+            }
+        }
+        if idle {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    let _ = daemon.shutdown();
+    Ok(services)
+}
+
+/// 在指定时间内浏览局域网的无线调试服务，每解析出一个服务调用一次 `on_found`
+///
+/// 阻塞调用，在 async 上下文中请放到 `spawn_blocking` 中执行。
+pub fn browse(
+    timeout: Duration,
+    cancel: &AtomicBool,
+    mut on_found: impl FnMut(&MdnsService),
+) -> Result<Vec<MdnsService>> {
+    run(timeout, cancel, |_, resolved| {
+        if let Some(service) = resolved {
+            on_found(service);
+        }
+        false
+    })
+}
+
+/// 等待第一个满足条件的服务出现，超时或取消时返回 `None`
+///
+/// 阻塞调用，在 async 上下文中请放到 `spawn_blocking` 中执行。
+pub fn wait_for(
+    timeout: Duration,
+    cancel: &AtomicBool,
+    matches: impl Fn(&MdnsService) -> bool,
+) -> Result<Option<MdnsService>> {
+    let mut found = None;
+    run(timeout, cancel, |services, _| {
+        found = services.iter().find(|s| matches(s)).cloned();
+        found.is_some()
+    })?;
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_name() {
+        assert_eq!(
+            instance_name("adb-R58M123-AbCdEf._adb-tls-connect._tcp.local.", CONNECT_SERVICE),
+            "adb-R58M123-AbCdEf"
+        );
+        assert_eq!(instance_name("other", PAIRING_SERVICE), "other");
+
+        let service = MdnsService {
+            kind: MdnsServiceKind::Connect,
+            name: "adb-R58M123-AbCdEf".into(),
+            host: "Android.local.".into(),
+            ip: "192.168.1.5".into(),
+            port: 37123,
+        };
+        assert_eq!(service.addr(), "192.168.1.5:37123");
+    }
+}
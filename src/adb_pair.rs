@@ -0,0 +1,195 @@
+//! Scrcpy Launcher - 扫码配对模块
+//!
+//! 与 Android Studio 的"扫码配对"相同的流程 (Android 11+)：
+//! 1. 生成随机服务名和密码，编码为 `WIFI:T:ADB;S:<服务名>;P:<密码>;;` 二维码
+//! 2. 手机在"无线调试 -> 使用二维码配对设备"中扫码后，会以该服务名广播 `_adb-tls-pairing._tcp`
+//! 3. 发现该服务后执行 `adb pair <地址> <密码>`
+//! 4. 配对成功后等待同一 IP 的 `_adb-tls-connect._tcp` 服务并自动连接
+//!
+//! 进度通过 `qr-pair-progress` 事件推送。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{Result, Context};
+use base64::Engine;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use rsa::rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+use crate::adb_mdns::{self, MdnsServiceKind};
+use crate::scrcpy;
+
+/// 等待手机扫码的时间
+const SCAN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 配对后等待连接服务出现的时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 服务名前缀
+const SERVICE_PREFIX: &str = "scrcpy-launcher-";
+
+/// 当前配对会话的取消标记 (同一时间只有一个会话)
+static CURRENT: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// 一次扫码配对的信息
+#[derive(Serialize, Clone, Debug)]
+pub struct QrPairing {
+    /// mDNS 服务名
+    pub name: String,
+    /// 配对密码
+    pub password: String,
+    /// 二维码内容
+    pub payload: String,
+    /// 二维码图片 (SVG data URL)
+    pub qr: String,
+}
+
+/// 配对阶段
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PairStage {
+    /// 等待手机扫码
+    Waiting,
+    /// 已发现手机，正在配对
+    Pairing,
+    /// 配对成功，正在连接
+    Connecting,
+    /// 已连接
+    Connected,
+    Failed,
+    Cancelled,
+}
+
+/// 进度事件载荷
+#[derive(Serialize, Clone, Debug)]
+struct PairProgress {
+    name: String,
+    stage: PairStage,
+    message: String,
+}
+
+/// 生成随机字符串 (字母和数字)
+fn random_string(len: usize) -> String {
+    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| CHARSET[*b as usize % CHARSET.len()] as char).collect()
+}
+
+/// 二维码内容
+pub fn qr_payload(name: &str, password: &str) -> String {
+    format!("WIFI:T:ADB;S:{};P:{};;", name, password)
+}
+
+/// 将内容渲染为 SVG 二维码的 data URL
+pub fn qr_data_url(payload: &str) -> Result<String> {
+    let code = QrCode::new(payload.as_bytes()).context("生成二维码失败")?;
+    let image = code
+        .render::<svg::Color>()
+        .min_dimensions(240, 240)
+        .quiet_zone(true)
+        .build();
+    Ok(format!(
+        "data:image/svg+xml;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(image)
+    ))
+}
+
+impl QrPairing {
+    /// 生成新的服务名、密码和二维码
+    pub fn generate() -> Result<Self> {
+        let name = format!("{}{}", SERVICE_PREFIX, random_string(8));
+        let password = random_string(12);
+        let payload = qr_payload(&name, &password);
+        let qr = qr_data_url(&payload)?;
+        Ok(Self { name, password, payload, qr })
+    }
+}
+
+/// 开始扫码配对，返回二维码；之后的进度通过事件推送
+///
+/// 会取消上一个尚未结束的配对会话。
+pub fn start<R: Runtime>(app: AppHandle<R>) -> Result<QrPairing> {
+    let pairing = QrPairing::generate()?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = CURRENT.lock().unwrap().replace(cancel.clone()) {
+        previous.store(true, Ordering::Relaxed);
+    }
+
+    let session = pairing.clone();
+    std::thread::spawn(move || {
+        let emit = |stage: PairStage, message: String| {
+            let _ = app.emit("qr-pair-progress", PairProgress { name: session.name.clone(), stage, message });
+        };
+        let (stage, message) = match run(&session, &cancel, &emit) {
+            Ok(message) => (PairStage::Connected, message),
+            Err(_) if cancel.load(Ordering::Relaxed) => (PairStage::Cancelled, "已取消".to_string()),
+            Err(e) => (PairStage::Failed, format!("{:#}", e)),
+        };
+        emit(stage, message);
+
+        let mut current = CURRENT.lock().unwrap();
+        if current.as_ref().is_some_and(|c| Arc::ptr_eq(c, &cancel)) {
+            *current = None;
+        }
+    });
+    Ok(pairing)
+}
+
+/// 取消当前的配对会话
+pub fn cancel() -> bool {
+    match CURRENT.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 执行配对流程，成功时返回连接结果
+fn run(session: &QrPairing, cancel: &AtomicBool, emit: &impl Fn(PairStage, String)) -> Result<String> {
+    emit(PairStage::Waiting, "请在手机上扫描二维码".to_string());
+    let service = adb_mdns::wait_for(SCAN_TIMEOUT, cancel, |s| {
+        s.kind == MdnsServiceKind::Pairing && s.name == session.name
+    })?
+    .context("等待扫码超时")?;
+
+    emit(PairStage::Pairing, format!("正在配对 {}", service.addr()));
+    let result = scrcpy::pair_device(&service.addr(), &session.password);
+    if !result.success {
+        anyhow::bail!("{}", result.message.trim());
+    }
+
+    emit(PairStage::Connecting, format!("配对成功，正在连接 {}", service.ip));
+    let connect = adb_mdns::wait_for(CONNECT_TIMEOUT, cancel, |s| {
+        s.kind == MdnsServiceKind::Connect && s.ip == service.ip
+    })?
+    .context("配对成功，但未发现连接端口，请在手机上确认无线调试已开启")?;
+
+    let result = tauri::async_runtime::block_on(scrcpy::connect_wireless(&connect.addr()));
+    if !result.success {
+        anyhow::bail!("{}", result.message.trim());
+    }
+    Ok(format!("已连接 {}", connect.addr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qr_pairing() {
+        let pairing = QrPairing::generate().unwrap();
+        assert!(pairing.name.starts_with(SERVICE_PREFIX));
+        assert_eq!(pairing.password.len(), 12);
+        assert!(pairing.password.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(
+            pairing.payload,
+            format!("WIFI:T:ADB;S:{};P:{};;", pairing.name, pairing.password)
+        );
+        assert!(pairing.qr.starts_with("data:image/svg+xml;base64,"));
+    }
+}
@@ -0,0 +1,255 @@
+//! Scrcpy Launcher - ADB Shell 模块
+//!
+//! 原生 shell 协议客户端，取代启动 adb.exe 并从输出文本判断成败：
+//! - 设备支持 `shell_v2` 时使用 `shell,v2,raw:`，stdout/stderr 分开，退出代码来自协议
+//! - 旧设备回退到 `shell:`，在命令末尾输出退出代码标记，stderr 混在 stdout 中
+//!
+//! 与 `adb_host` 一样使用阻塞 socket；设备设置为直连时通过 `adb_transport` 打开服务。
+
+use std::io::Read;
+use std::net::TcpStream;
+use std::sync::OnceLock;
+use anyhow::{Result, bail, Context};
+use serde::Serialize;
+use crate::adb_host::{self, AdbHost};
+use crate::adb_transport::{self, AdbBackend};
+
+/// shell v2 数据包类型
+const ID_STDOUT: u8 = 1;
+const ID_STDERR: u8 = 2;
+const ID_EXIT: u8 = 3;
+
+/// 旧版 shell 的退出代码标记
+const EXIT_MARKER: &[u8] = b"__ADB_EXIT__";
+/// 流式输出时保留的尾部长度，保证标记不会被拆开输出
+const MARKER_HOLD_BACK: usize = 32;
+
+/// 输出来源
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellStream {
+    Stdout,
+    Stderr,
+}
+
+/// shell 命令的执行结果
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ShellOutput {
+    pub stdout: String,
+    /// 旧版 shell 协议下始终为空 (stderr 混在 stdout 中)
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+impl ShellOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    /// 失败时用于展示的错误信息：优先 stderr，其次 stdout
+    pub fn error_message(&self) -> String {
+        [&self.stderr, &self.stdout]
+            .into_iter()
+            .map(|s| s.trim())
+            .find(|s| !s.is_empty())
+            .map(String::from)
+            .unwrap_or_else(|| format!("退出代码 {}", self.exit_code))
+    }
+}
+
+/// 为 shell 命令参数加单引号
+pub fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// 设备 shell 客户端
+pub struct AdbShell {
+    device_serial: Option<String>,
+    host: AdbHost,
+    backend: AdbBackend,
+    shell_v2: OnceLock<bool>,
+}
+
+impl AdbShell {
+    pub fn new(device_serial: Option<String>) -> Self {
+        Self {
+            backend: adb_transport::backend_for(device_serial.as_deref()),
+            host: AdbHost::for_device(device_serial.as_deref()),
+            device_serial,
+            shell_v2: OnceLock::new(),
+        }
+    }
+
+    /// 设备是否支持 shell 协议 v2 (结果会被缓存)
+    pub fn supports_v2(&self) -> bool {
+        *self.shell_v2.get_or_init(|| {
+            let features = match (self.backend, &self.device_serial) {
+                (AdbBackend::Direct, Some(serial)) => adb_transport::connection(serial).map(|c| c.banner().features.clone()),
+                _ => self.host.features(self.device_serial.as_deref()),
+            };
+            features.is_ok_and(|f| f.iter().any(|x| x == "shell_v2"))
+        })
+    }
+
+    /// 打开设备上的服务
+    fn open(&self, service: &str) -> Result<Box<dyn Read>> {
+        match (self.backend, &self.device_serial) {
+            (AdbBackend::Direct, Some(serial)) => Ok(Box::new(adb_transport::connection(serial)?.open(service)?)),
+            _ => Ok(Box::new(self.open_via_server(service)?)),
+        }
+    }
+
+    /// 通过 ADB Server 切换到设备并打开服务
+    fn open_via_server(&self, service: &str) -> Result<TcpStream> {
+        let target = match &self.device_serial {
+            Some(s) => format!("host:transport:{}", s),
+            None => "host:transport-any".to_string(),
+        };
+        let mut stream = self.host.request(&target).context("设备连接失败或未授权")?;
+        adb_host::send_packet(&mut stream, service)?;
+        adb_host::read_status(&mut stream)?;
+        // shell 命令可能长时间没有输出
+        stream.set_read_timeout(None)?;
+        Ok(stream)
+    }
+
+    /// 执行 shell 命令并收集全部输出
+    pub fn run(&self, command: &str) -> Result<ShellOutput> {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let exit_code = self.run_streaming(command, &mut |stream, data| match stream {
+            ShellStream::Stdout => stdout.extend_from_slice(data),
+            ShellStream::Stderr => stderr.extend_from_slice(data),
+        })?;
+
+        Ok(ShellOutput {
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+            exit_code,
+        })
+    }
+
+    /// 执行 shell 命令，输出到达时通过回调传出，返回退出代码
+    pub fn run_streaming(&self, command: &str, on_output: &mut dyn FnMut(ShellStream, &[u8])) -> Result<i32> {
+        if self.supports_v2() {
+            let mut stream = self.open(&format!("shell,v2,raw:{}", command))?;
+            read_v2(&mut stream, on_output)
+        } else {
+            // 换行分隔，兼容以 & 结尾的命令
+            let wrapped = format!("{}\n__rc=$?; echo; echo {}$__rc", command, String::from_utf8_lossy(EXIT_MARKER));
+            let mut stream = self.open(&format!("shell:{}", wrapped))?;
+            read_legacy(&mut stream, on_output)
+        }
+    }
+
+    /// 执行一次性设备服务 (如 `tcpip:5555`)，返回设备的应答文本
+    pub fn service(&self, service: &str) -> Result<String> {
+        let mut stream = self.open(service)?;
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply)?;
+        Ok(String::from_utf8_lossy(&reply).to_string())
+    }
+}
+
+/// 读取 shell v2 数据包直到收到退出代码
+///
+/// 数据包格式: id (1 字节) + 长度 (u32 LE) + 数据
+fn read_v2<R: Read>(reader: &mut R, on_output: &mut dyn FnMut(ShellStream, &[u8])) -> Result<i32> {
+    let mut header = [0u8; 5];
+    loop {
+        reader.read_exact(&mut header).context("shell 连接意外断开")?;
+        let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        let mut data = vec![0u8; len];
+        reader.read_exact(&mut data)?;
+
+        match header[0] {
+            ID_STDOUT => on_output(ShellStream::Stdout, &data),
+            ID_STDERR => on_output(ShellStream::Stderr, &data),
+            ID_EXIT => return Ok(data.first().copied().unwrap_or(0) as i32),
+            _ => {}
+        }
+    }
+}
+
+/// 读取旧版 shell 输出直到连接关闭，从末尾的标记中解析退出代码
+fn read_legacy<R: Read>(reader: &mut R, on_output: &mut dyn FnMut(ShellStream, &[u8])) -> Result<i32> {
+    let mut pending = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buf[..n]);
+        if pending.len() > MARKER_HOLD_BACK {
+            let ready = pending.len() - MARKER_HOLD_BACK;
+            on_output(ShellStream::Stdout, &pending[..ready]);
+            pending.drain(..ready);
+        }
+    }
+
+    let Some(pos) = pending.windows(EXIT_MARKER.len()).rposition(|w| w == EXIT_MARKER) else {
+        bail!("shell 连接意外断开");
+    };
+    let exit_code = String::from_utf8_lossy(&pending[pos + EXIT_MARKER.len()..])
+        .trim()
+        .parse()
+        .context("无效的退出代码")?;
+
+    // 去掉标记前额外输出的换行 (pty 下为 \r\n)
+    let mut end = pos;
+    for newline in [&b"\r\n"[..], b"\n"] {
+        if pending[..end].ends_with(newline) {
+            end -= newline.len();
+            break;
+        }
+    }
+    if end > 0 {
+        on_output(ShellStream::Stdout, &pending[..end]);
+    }
+    Ok(exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(read: impl FnOnce(&mut dyn FnMut(ShellStream, &[u8])) -> Result<i32>) -> (String, String, i32) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let code = read(&mut |s, d| match s {
+            ShellStream::Stdout => out.extend_from_slice(d),
+            ShellStream::Stderr => err.extend_from_slice(d),
+        }).unwrap();
+        (String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap(), code)
+    }
+
+    #[test]
+    fn test_read_v2() {
+        let mut data = Vec::new();
+        for (id, payload) in [(ID_STDOUT, &b"package:a\n"[..]), (ID_STDERR, b"warn\n"), (ID_STDOUT, b"package:b\n"), (ID_EXIT, &[3])] {
+            data.push(id);
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(payload);
+        }
+
+        let (out, err, code) = collect(|cb| read_v2(&mut data.as_slice(), cb));
+        assert_eq!(out, "package:a\npackage:b\n");
+        assert_eq!(err, "warn\n");
+        assert_eq!(code, 3);
+    }
+
+    #[test]
+    fn test_read_legacy() {
+        let long = "x".repeat(100);
+        let data = format!("{}\r\nline\r\n\r\n__ADB_EXIT__1\r\n", long);
+        let (out, _, code) = collect(|cb| read_legacy(&mut data.as_bytes(), cb));
+        assert_eq!(out, format!("{}\r\nline\r\n", long));
+        assert_eq!(code, 1);
+
+        let (out, _, code) = collect(|cb| read_legacy(&mut &b"\n__ADB_EXIT__0\n"[..], cb));
+        assert_eq!(out, "");
+        assert_eq!(code, 0);
+
+        assert!(read_legacy(&mut &b"partial output"[..], &mut |_, _| {}).is_err());
+    }
+}
@@ -1,24 +1,371 @@
-use std::path::Path;
-use tokio::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::fs;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Notify, OnceCell};
+use crate::adb_compress::{self, Compression};
+use crate::adb_host;
+use crate::adb_shell::{shell_quote, AdbShell};
+use crate::adb_transport::{self, AdbBackend, AdbStream};
 use anyhow::{Result, bail, Context};
 
 /// 定义进度回调的类型：已传输字节, 总字节
 pub type ProgressCallback = Box<dyn Fn(u64, u64) + Send + Sync>;
 
+/// 文件类型掩码与类型位 (与 Linux stat 的 st_mode 一致)
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+/// 设备上的文件/目录信息 (来自 SYNC STAT/LIST)
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub mode: u32,
+    pub size: u64,
+    /// 修改时间 (Unix 秒)
+    pub mtime: i64,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+}
+
+impl RemoteEntry {
+    fn new(name: String, mode: u32, size: u64, mtime: i64) -> Self {
+        Self {
+            name,
+            mode,
+            size,
+            mtime,
+            is_dir: mode & S_IFMT == S_IFDIR,
+            is_symlink: mode & S_IFMT == S_IFLNK,
+        }
+    }
+}
+
+/// SYNC 协议版本
+#[derive(serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncProtocol {
+    /// SEND/RECV
+    #[default]
+    V1,
+    /// SND2/RCV2，支持压缩
+    V2,
+}
+
+/// 协商后的传输方式
+#[derive(serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncMode {
+    pub protocol: SyncProtocol,
+    pub compression: Compression,
+}
+
+/// 单次传输的统计信息
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct TransferStats {
+    pub protocol: SyncProtocol,
+    pub compression: Compression,
+    /// 文件原始字节数
+    pub bytes: u64,
+    /// 实际经过连接的 DATA 字节数 (压缩后)
+    pub wire_bytes: u64,
+    pub elapsed_ms: u64,
+    /// 有效吞吐量 (原始字节/秒)
+    pub throughput: f64,
+}
+
+impl TransferStats {
+    fn new(mode: SyncMode, bytes: u64, wire_bytes: u64, started: Instant) -> Self {
+        let elapsed = started.elapsed();
+        let secs = elapsed.as_secs_f64();
+        Self {
+            protocol: mode.protocol,
+            compression: mode.compression,
+            bytes,
+            wire_bytes,
+            elapsed_ms: elapsed.as_millis() as u64,
+            throughput: if secs > 0.0 { bytes as f64 / secs } else { 0.0 },
+        }
+    }
+}
+
+impl std::fmt::Display for TransferStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let protocol = match self.protocol {
+            SyncProtocol::V1 => "v1",
+            SyncProtocol::V2 => "v2",
+        };
+        let compression = match self.compression {
+            Compression::None => "无压缩",
+            Compression::Brotli => "brotli",
+            Compression::Lz4 => "lz4",
+            Compression::Zstd => "zstd",
+        };
+        write!(f, "{}/{}, {:.1} MB/s", protocol, compression, self.throughput / 1024.0 / 1024.0)
+    }
+}
+
+/// 目录推送结果汇总
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct PushSummary {
+    pub files_total: usize,
+    pub files_pushed: usize,
+    pub bytes_total: u64,
+    pub bytes_pushed: u64,
+    pub empty_dirs_created: usize,
+    /// 被跳过的符号链接 (相对路径)
+    pub skipped: Vec<String>,
+    pub failures: Vec<PushFailure>,
+    pub stats: TransferStats,
+}
+
+/// 单个文件的推送失败信息
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct PushFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// 待推送的文件
+struct PushEntry {
+    local: PathBuf,
+    /// 以 `/` 分隔的相对路径
+    relative: String,
+    size: u64,
+    mode: u32,
+    mtime: u32,
+}
+
+/// SEND 请求携带的文件元数据
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FileMeta {
+    /// 包含文件类型位的完整 st_mode
+    mode: u32,
+    /// 修改时间 (Unix 秒)
+    mtime: u32,
+}
+
+impl FileMeta {
+    /// 从本地文件元数据提取权限和修改时间，`mode_override` 只替换权限位
+    fn from_metadata(metadata: &std::fs::Metadata, mode_override: Option<u32>) -> Self {
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or(0);
+        Self {
+            mode: Self::regular(mode_override.unwrap_or_else(|| local_permissions(metadata))),
+            mtime,
+        }
+    }
+
+    /// 普通文件的 st_mode (S_IFREG | 权限位)
+    fn regular(permissions: u32) -> u32 {
+        S_IFREG | (permissions & 0o7777)
+    }
+}
+
+/// 本地文件的权限位
+#[cfg(unix)]
+fn local_permissions(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+/// 本地文件的权限位 (Windows 没有可执行位，只区分只读)
+#[cfg(not(unix))]
+fn local_permissions(metadata: &std::fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() { 0o444 } else { 0o644 }
+}
+
+/// 目录遍历结果
+#[derive(Default)]
+struct PushPlan {
+    files: Vec<PushEntry>,
+    empty_dirs: Vec<String>,
+    skipped: Vec<String>,
+}
+
+/// 遍历本地目录，收集文件、空目录和符号链接
+///
+/// 相对路径不含根目录名 (远程目录已经是目标目录本身)，根目录为空时记为 `""`
+fn collect_push_entries(root: &Path) -> Result<PushPlan> {
+    let mut plan = PushPlan::default();
+    walk_dir(root, "", &mut plan)?;
+    Ok(plan)
+}
+
+/// 拼接远程目录和相对路径
+fn remote_join(remote_dir: &str, relative: &str) -> String {
+    match relative {
+        "" => remote_dir.to_string(),
+        _ => format!("{}/{}", remote_dir, relative),
+    }
+}
+
+fn walk_dir(dir: &Path, relative: &str, plan: &mut PushPlan) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {}", dir.display()))?
+        .collect::<std::io::Result<_>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    let mut has_children = false;
+    for entry in entries {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let child_relative = if relative.is_empty() { file_name } else { format!("{}/{}", relative, file_name) };
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            plan.skipped.push(child_relative);
+        } else if file_type.is_dir() {
+            has_children = true;
+            walk_dir(&entry.path(), &child_relative, plan)?;
+        } else if file_type.is_file() {
+            has_children = true;
+            let metadata = entry.metadata()?;
+            let meta = FileMeta::from_metadata(&metadata, None);
+            plan.files.push(PushEntry {
+                local: entry.path(),
+                relative: child_relative,
+                size: metadata.len(),
+                mode: meta.mode,
+                mtime: meta.mtime,
+            });
+        }
+    }
+
+    if !has_children {
+        plan.empty_dirs.push(relative.to_string());
+    }
+    Ok(())
+}
+
+/// Linux ENOENT 错误码
+const ENOENT: u32 = 2;
+
+/// STA2/LIS2 返回的 stat 结构 (ID 之后、文件名之前的部分)
+struct StatV2 {
+    error: u32,
+    mode: u32,
+    size: u64,
+    mtime: i64,
+}
+
+/// 读取 v2 stat 结构: error, dev, ino, mode, nlink, uid, gid, size, atime, mtime, ctime
+async fn read_stat_v2(stream: &mut AdbStream) -> Result<StatV2> {
+    let mut buf = [0u8; 68];
+    stream.read_exact(&mut buf).await?;
+    Ok(parse_stat_v2(&buf))
+}
+
+fn parse_stat_v2(buf: &[u8; 68]) -> StatV2 {
+    let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+    let u64_at = |i: usize| u64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+    StatV2 {
+        error: u32_at(0),
+        mode: u32_at(20),
+        size: u64_at(36),
+        mtime: u64_at(52) as i64,
+    }
+}
+
+/// 解析逗号分隔的特性列表
+pub(crate) fn parse_features(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+/// 传输取消令牌，可在多个任务间共享
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<CancelInner>,
+}
+
+#[derive(Default)]
+struct CancelInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消，正在等待的传输会立即结束
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待直到被取消
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // 先注册等待再检查标志，避免错过 cancel() 的通知
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// 传输被取消时返回的错误，可通过 `anyhow::Error::is` 识别
+#[derive(Debug)]
+pub struct TransferCancelled;
+
+impl std::fmt::Display for TransferCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "传输已取消")
+    }
+}
+
+impl std::error::Error for TransferCancelled {}
+
 pub struct AdbPusher {
     device_serial: Option<String>,
+    backend: AdbBackend,
+    /// 设备所在 ADB Server 的地址
+    server_addr: String,
+    /// 设备支持的特性列表 (首次使用时查询)
+    features: OnceCell<Vec<String>>,
+    cancel: CancelToken,
 }
 
 impl AdbPusher {
     pub fn new(serial: Option<String>) -> Self {
-        Self { device_serial: serial }
+        Self {
+            backend: adb_transport::backend_for(serial.as_deref()),
+            server_addr: adb_host::server_for(serial.as_deref()).addr(),
+            device_serial: serial,
+            features: OnceCell::new(),
+            cancel: CancelToken::new(),
+        }
+    }
+
+    /// 绑定取消令牌，取消后传输会断开 SYNC 连接并返回 `TransferCancelled`
+    ///
+    /// adbd 在连接中断时会删除未写完的远程文件，pull 则由本端删除不完整的本地文件。
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
     }
 
     /// 连接 ADB Server 并建立 Transport
     async fn connect(&self) -> Result<TcpStream> {
-        let mut stream = TcpStream::connect("127.0.0.1:5037").await
+        let mut stream = TcpStream::connect(&self.server_addr).await
             .context("无法连接到 ADB Server，请确保 adb start-server 已运行")?;
 
         // 1. 切换到指定设备 (host:transport:serial)
@@ -61,56 +408,578 @@ impl AdbPusher {
         }
     }
 
+    /// 打开设备上的服务：直连设备时在直连上打开流，否则经 ADB Server 转发
+    async fn open(&self, service: &str) -> Result<AdbStream> {
+        if let (AdbBackend::Direct, Some(serial)) = (self.backend, &self.device_serial) {
+            let conn = adb_transport::connection_async(serial).await?;
+            return Ok(Box::new(conn.open_async(service).await?));
+        }
+
+        let mut stream = self.connect().await?;
+        self.send_packet(&mut stream, service).await?;
+        self.read_status(&mut stream).await?;
+        Ok(Box::new(stream))
+    }
+
+    /// 连接设备并进入 SYNC 模式
+    async fn open_sync(&self) -> Result<AdbStream> {
+        self.open("sync:").await.context("无法进入 SYNC 模式")
+    }
+
+    /// 连接设备并打开服务 (如 `exec:cmd package ...`)
+    pub(crate) async fn open_service(&self, service: &str) -> Result<AdbStream> {
+        self.open(service).await.with_context(|| format!("设备拒绝服务: {}", service))
+    }
+
+    /// 发送 SYNC 请求 (4字节 ID + 小端长度 + 路径)
+    async fn send_sync_request(&self, stream: &mut AdbStream, id: &[u8; 4], path: &str) -> Result<()> {
+        let path_bytes = path.as_bytes();
+        stream.write_all(id).await?;
+        stream.write_u32_le(path_bytes.len() as u32).await?;
+        stream.write_all(path_bytes).await?;
+        Ok(())
+    }
+
+    /// 读取 SYNC FAIL 帧携带的错误信息 (ID 已被读取)
+    async fn read_sync_fail(&self, stream: &mut AdbStream) -> Result<String> {
+        let len = stream.read_u32_le().await?;
+        let mut msg = vec![0u8; len as usize];
+        stream.read_exact(&mut msg).await?;
+        Ok(String::from_utf8_lossy(&msg).to_string())
+    }
+
+    /// 读取 ADB 格式的应答数据 (4字节十六进制长度 + 内容)
+    async fn read_length_prefixed(&self, stream: &mut TcpStream) -> Result<String> {
+        let mut len_buf = [0u8; 4];
+        stream.read_exact(&mut len_buf).await?;
+        let len = usize::from_str_radix(&String::from_utf8_lossy(&len_buf), 16)
+            .context("无效的应答长度")?;
+        let mut data = vec![0u8; len];
+        stream.read_exact(&mut data).await?;
+        Ok(String::from_utf8_lossy(&data).to_string())
+    }
+
+    /// 查询设备支持的特性 (host:features)，结果会被缓存
+    pub async fn features(&self) -> Result<&[String]> {
+        let features = self.features.get_or_try_init(|| async {
+            if let (AdbBackend::Direct, Some(serial)) = (self.backend, &self.device_serial) {
+                return Ok(adb_transport::connection_async(serial).await?.banner().features.clone());
+            }
+            let mut stream = TcpStream::connect(&self.server_addr).await
+                .context("无法连接到 ADB Server，请确保 adb start-server 已运行")?;
+            let request = match &self.device_serial {
+                Some(s) => format!("host-serial:{}:features", s),
+                None => "host:features".to_string(),
+            };
+            self.send_packet(&mut stream, &request).await?;
+            self.read_status(&mut stream).await.context("无法获取设备特性")?;
+            let list = self.read_length_prefixed(&mut stream).await?;
+            Ok::<_, anyhow::Error>(parse_features(&list))
+        }).await?;
+        Ok(features)
+    }
+
+    /// 设备是否支持指定特性
+    pub(crate) async fn has_feature(&self, name: &str) -> bool {
+        self.features().await
+            .map(|f| f.iter().any(|x| x == name))
+            .unwrap_or(false)
+    }
+
+    /// 在已进入 SYNC 模式的连接上执行 STAT/STA2，路径不存在时返回 None
+    async fn sync_stat(&self, stream: &mut AdbStream, remote_path: &str, v2: bool) -> Result<Option<RemoteEntry>> {
+        let name = remote_path.trim_end_matches('/').rsplit('/').next().unwrap_or("").to_string();
+
+        if v2 {
+            self.send_sync_request(stream, b"STA2", remote_path).await?;
+            let mut id = [0u8; 4];
+            stream.read_exact(&mut id).await?;
+            if &id != b"STA2" {
+                bail!("STA2 响应无效: {}", String::from_utf8_lossy(&id));
+            }
+            let stat = read_stat_v2(stream).await?;
+            return match stat.error {
+                0 => Ok(Some(RemoteEntry::new(name, stat.mode, stat.size, stat.mtime))),
+                ENOENT => Ok(None),
+                errno => bail!("STAT 失败 (errno {}): {}", errno, remote_path),
+            };
+        }
+
+        self.send_sync_request(stream, b"STAT", remote_path).await?;
+        let mut id = [0u8; 4];
+        stream.read_exact(&mut id).await?;
+        if &id != b"STAT" {
+            bail!("STAT 响应无效: {}", String::from_utf8_lossy(&id));
+        }
+        let mode = stream.read_u32_le().await?;
+        let size = stream.read_u32_le().await?;
+        let mtime = stream.read_u32_le().await?;
+
+        // v1 中 mode 为 0 表示远程路径不存在
+        Ok(if mode == 0 { None } else { Some(RemoteEntry::new(name, mode, size as u64, mtime as i64)) })
+    }
+
+    /// 查询远程路径信息，优先使用 STA2 (支持 4GB 以上文件)
+    pub async fn stat(&self, remote_path: &str) -> Result<Option<RemoteEntry>> {
+        let v2 = self.has_feature("stat_v2").await;
+        let mut stream = self.open_sync().await?;
+        self.sync_stat(&mut stream, remote_path, v2).await
+    }
+
+    /// 列出远程目录内容，优先使用 LIS2
+    ///
+    /// 结果不包含 `.` 和 `..`，目录排在前面，同类按名称排序。
+    pub async fn list(&self, remote_dir: &str) -> Result<Vec<RemoteEntry>> {
+        let v2 = self.has_feature("ls_v2").await;
+        let mut stream = self.open_sync().await?;
+        self.send_sync_request(&mut stream, if v2 { b"LIS2" } else { b"LIST" }, remote_dir).await?;
+
+        let mut entries = Vec::new();
+        loop {
+            let mut id = [0u8; 4];
+            stream.read_exact(&mut id).await?;
+
+            let (done, mode, size, mtime) = match &id {
+                b"DNT2" | b"DONE" if v2 => {
+                    let stat = read_stat_v2(&mut stream).await?;
+                    (&id == b"DONE", stat.mode, stat.size, stat.mtime)
+                }
+                b"DENT" | b"DONE" => {
+                    let mode = stream.read_u32_le().await?;
+                    let size = stream.read_u32_le().await?;
+                    let mtime = stream.read_u32_le().await?;
+                    (&id == b"DONE", mode, size as u64, mtime as i64)
+                }
+                b"FAIL" => {
+                    let msg = self.read_sync_fail(&mut stream).await?;
+                    bail!("列出目录失败: {}", msg);
+                }
+                other => bail!("未知的 SYNC 响应: {}", String::from_utf8_lossy(other)),
+            };
+
+            let name_len = stream.read_u32_le().await? as usize;
+            if done {
+                break;
+            }
+            let mut name = vec![0u8; name_len];
+            stream.read_exact(&mut name).await?;
+            let name = String::from_utf8_lossy(&name).to_string();
+
+            if name != "." && name != ".." {
+                entries.push(RemoteEntry::new(name, mode, size, mtime));
+            }
+        }
+
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        Ok(entries)
+    }
+
+    /// 删除远程文件或目录 (rm -rf)
+    pub async fn delete(&self, remote_path: &str) -> Result<()> {
+        self.shell_silent(&format!("rm -rf {}", shell_quote(remote_path))).await
+    }
+
+    /// 创建远程目录 (mkdir -p)
+    pub async fn mkdir(&self, remote_path: &str) -> Result<()> {
+        self.shell_silent(&format!("mkdir -p {}", shell_quote(remote_path))).await
+    }
+
+    /// 重命名/移动远程文件 (mv)
+    pub async fn rename(&self, from: &str, to: &str) -> Result<()> {
+        self.shell_silent(&format!("mv {} {}", shell_quote(from), shell_quote(to))).await
+    }
+
+    /// 执行 shell 命令，退出代码非 0 时返回其错误输出
+    async fn shell_silent(&self, command: &str) -> Result<()> {
+        let shell = AdbShell::new(self.device_serial.clone());
+        let command = command.to_string();
+        let output = tokio::task::spawn_blocking(move || shell.run(&command)).await??;
+        if !output.success() {
+            bail!("{}", output.error_message());
+        }
+        Ok(())
+    }
+
+    /// 协商 SYNC 协议版本和压缩算法
+    ///
+    /// 设备支持 `sendrecv_v2` 时使用 SND2/RCV2，并按设备特性选择压缩算法；否则回退到 v1。
+    pub async fn negotiate(&self) -> SyncMode {
+        match self.features().await {
+            Ok(features) if features.iter().any(|f| f == "sendrecv_v2") => SyncMode {
+                protocol: SyncProtocol::V2,
+                compression: Compression::negotiate(features),
+            },
+            _ => SyncMode::default(),
+        }
+    }
+
     /// 核心功能：带进度的 Push
-    pub async fn push(&self, local_path: &str, remote_path: &str, callback: Option<ProgressCallback>) -> Result<()> {
+    ///
+    /// 默认沿用本地文件的权限和修改时间，`mode` 可显式覆盖权限位 (如 `0o755`)。
+    pub async fn push(&self, local_path: &str, remote_path: &str, mode: Option<u32>, callback: Option<ProgressCallback>) -> Result<TransferStats> {
         let path = Path::new(local_path);
-        let file_size = path.metadata()?.len();
-        let mut file = File::open(path).await?;
-        let mut buffer = [0u8; 64 * 1024]; // 64KB Chunk size (ADB 推荐)
+        let metadata = path.metadata()?;
+        let file_size = metadata.len();
+        let meta = FileMeta::from_metadata(&metadata, mode);
+        let file = std::fs::File::open(path)?;
+
+        // 1. 协商协议，连接并进入 SYNC 模式
+        let sync_mode = self.negotiate().await;
+        let started = Instant::now();
+        let mut stream = self.open_sync().await?;
+
+        // 2. 发送文件，进度按单个文件计算
+        let wire_bytes = self.send_file(&mut stream, file, remote_path, meta, sync_mode, &mut |total_sent| {
+            if let Some(cb) = &callback {
+                cb(total_sent, file_size);
+            }
+        }).await?;
+
+        Ok(TransferStats::new(sync_mode, file_size, wire_bytes, started))
+    }
+
+    /// 在已进入 SYNC 模式的连接上发送单个文件，返回实际传输的 DATA 字节数
+    ///
+    /// `on_progress` 收到的是当前文件已读取的原始字节数。
+    /// 返回错误后连接状态不可再复用 (adbd 在 FAIL 后会关闭 SYNC 会话)。
+    async fn send_file(
+        &self,
+        stream: &mut AdbStream,
+        file: std::fs::File,
+        remote_path: &str,
+        meta: FileMeta,
+        sync_mode: SyncMode,
+        on_progress: &mut (dyn FnMut(u64) + Send),
+    ) -> Result<u64> {
+        // 1. 发送请求头
+        match sync_mode.protocol {
+            SyncProtocol::V1 => {
+                // ID_SEND + 长度 + "远程路径,权限"
+                let path_and_mode = format!("{},{}", remote_path, meta.mode);
+                self.send_sync_request(stream, b"SEND", &path_and_mode).await?;
+            }
+            SyncProtocol::V2 => {
+                // ID_SND2 + 长度 + 远程路径，随后是 ID_SND2 + 权限 + 压缩标志
+                self.send_sync_request(stream, b"SND2", remote_path).await?;
+                stream.write_all(b"SND2").await?;
+                stream.write_u32_le(meta.mode).await?;
+                stream.write_u32_le(sync_mode.compression.flag()).await?;
+            }
+        }
+
+        // 2. 循环发送 DATA 数据块 (读取和压缩在后台线程中进行)
+        let (mut chunks, read_bytes, encoder) = adb_compress::spawn_encoder(file, sync_mode.compression);
+        let mut wire_bytes = 0u64;
 
-        // 1. 连接并进入 SYNC 模式
-        let mut stream = self.connect().await?;
-        self.send_packet(&mut stream, "sync:").await?;
-        self.read_status(&mut stream).await.context("无法进入 SYNC 模式")?;
-
-        // 2. 发送 SEND 请求 (ID_SEND + 长度 + 远程路径)
-        let remote_path_bytes = remote_path.as_bytes();
-        stream.write_all(b"SEND").await?;
-        stream.write_u32_le(remote_path_bytes.len() as u32).await?;
-        stream.write_all(remote_path_bytes).await?;
-
-        // 3. 循环发送 DATA 数据块
-        let mut total_sent = 0u64;
-        
         loop {
-            let n = file.read(&mut buffer).await?;
-            if n == 0 { break; } // 文件读完
+            let chunk = tokio::select! {
+                chunk = chunks.recv() => match chunk {
+                    Some(chunk) => chunk,
+                    None => break,
+                },
+                _ = self.cancel.cancelled() => return Err(TransferCancelled.into()),
+            };
 
             // 发送数据头: DATA + 块大小
             stream.write_all(b"DATA").await?;
-            stream.write_u32_le(n as u32).await?;
+            stream.write_u32_le(chunk.len() as u32).await?;
             // 发送数据体
-            stream.write_all(&buffer[..n]).await?;
+            stream.write_all(&chunk).await?;
 
             // === 触发进度回调 ===
-            total_sent += n as u64;
-            if let Some(cb) = &callback {
-                cb(total_sent, file_size);
-            }
+            wire_bytes += chunk.len() as u64;
+            on_progress(read_bytes.load(Ordering::Relaxed));
         }
+        encoder.await.context("压缩线程异常退出")?.context("读取本地文件失败")?;
 
-        // 4. 发送 DONE (结束 + 修改时间)
-        let time = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+        // 3. 发送 DONE (结束 + 修改时间)
         stream.write_all(b"DONE").await?;
-        stream.write_u32_le(time as u32).await?;
+        stream.write_u32_le(meta.mtime).await?;
 
-        // 5. 等待服务器确认 OKAY
+        // 4. 等待服务器确认 OKAY
         let mut resp = [0u8; 4];
         stream.read_exact(&mut resp).await?;
-        if &resp != b"OKAY" {
-            bail!("传输未被确认，可能失败");
+        match &resp {
+            b"OKAY" => {
+                // OKAY 后跟 4 字节占位
+                let _ = stream.read_u32_le().await?;
+                Ok(wire_bytes)
+            }
+            b"FAIL" => {
+                let msg = self.read_sync_fail(stream).await?;
+                bail!("传输失败: {}", msg);
+            }
+            _ => bail!("传输未被确认，可能失败"),
         }
+    }
 
-        Ok(())
+    /// 递归推送目录
+    ///
+    /// - 所有文件复用同一个 SYNC 连接，某个文件失败后才重新建立连接
+    /// - `remote_dir` 是目标目录本身，远程路径为 `remote_dir/<相对路径>`，父目录由 adbd 自动创建
+    /// - 空目录通过 `mkdir -p` 创建
+    /// - 符号链接不跟随，记录到 `skipped` 中
+    /// - 每个文件沿用本地权限和修改时间，`mode` 可统一覆盖权限位
+    /// - 进度回调按全部文件的总字节数汇总
+    pub async fn push_dir(&self, local_dir: &str, remote_dir: &str, mode: Option<u32>, callback: Option<ProgressCallback>) -> Result<PushSummary> {
+        let plan = collect_push_entries(Path::new(local_dir))?;
+        let remote_dir = remote_dir.trim_end_matches('/');
+
+        let sync_mode = self.negotiate().await;
+        let started = Instant::now();
+        let mut wire_bytes = 0u64;
+
+        let mut summary = PushSummary {
+            files_total: plan.files.len(),
+            bytes_total: plan.files.iter().map(|f| f.size).sum(),
+            skipped: plan.skipped,
+            ..Default::default()
+        };
+
+        let mut stream: Option<AdbStream> = None;
+        for entry in &plan.files {
+            let remote_path = remote_join(remote_dir, &entry.relative);
+
+            // 本地文件打不开时不影响 SYNC 连接，直接记录失败
+            let file = match std::fs::File::open(&entry.local) {
+                Ok(f) => f,
+                Err(e) => {
+                    summary.failures.push(PushFailure { path: entry.relative.clone(), error: e.to_string() });
+                    continue;
+                }
+            };
+
+            let conn = match stream.as_mut() {
+                Some(conn) => conn,
+                None => stream.insert(self.open_sync().await?),
+            };
+
+            let base = summary.bytes_pushed;
+            let bytes_total = summary.bytes_total;
+            let meta = FileMeta { mode: mode.map_or(entry.mode, FileMeta::regular), mtime: entry.mtime };
+            let result = self.send_file(conn, file, &remote_path, meta, sync_mode, &mut |sent| {
+                if let Some(cb) = &callback {
+                    cb(base + sent, bytes_total);
+                }
+            }).await;
+
+            match result {
+                Ok(sent) => {
+                    summary.files_pushed += 1;
+                    summary.bytes_pushed += entry.size;
+                    wire_bytes += sent;
+                }
+                Err(e) if e.is::<TransferCancelled>() => return Err(e),
+                Err(e) => {
+                    summary.failures.push(PushFailure { path: entry.relative.clone(), error: e.to_string() });
+                    // 连接状态未知，下一个文件重新建立
+                    stream = None;
+                }
+            }
+        }
+
+        // 创建空目录
+        if !plan.empty_dirs.is_empty() {
+            let targets: Vec<String> = plan.empty_dirs
+                .iter()
+                .map(|d| shell_quote(&remote_join(remote_dir, d)))
+                .collect();
+            match self.shell_silent(&format!("mkdir -p {}", targets.join(" "))).await {
+                Ok(_) => summary.empty_dirs_created = plan.empty_dirs.len(),
+                Err(e) => {
+                    for dir in plan.empty_dirs {
+                        summary.failures.push(PushFailure { path: dir, error: e.to_string() });
+                    }
+                }
+            }
+        }
+
+        summary.stats = TransferStats::new(sync_mode, summary.bytes_pushed, wire_bytes, started);
+        Ok(summary)
+    }
+
+    /// 核心功能：带进度的 Pull
+    ///
+    /// 进度回调与 `push` 一致：(已接收字节, 总字节)。
+    /// 总字节来自 STAT，对于 /proc 等大小未知的文件为 0。
+    pub async fn pull(&self, remote_path: &str, local_path: &str, callback: Option<ProgressCallback>) -> Result<TransferStats> {
+        // 1. 协商协议，连接并进入 SYNC 模式
+        let stat_v2 = self.has_feature("stat_v2").await;
+        let sync_mode = self.negotiate().await;
+        let started = Instant::now();
+        let mut stream = self.open_sync().await?;
+
+        // 2. 先 STAT 获取文件大小，用于计算进度
+        let file_size = match self.sync_stat(&mut stream, remote_path, stat_v2).await? {
+            Some(entry) if entry.is_dir => bail!("远程路径是目录: {}", remote_path),
+            Some(entry) => entry.size,
+            None => bail!("远程文件不存在: {}", remote_path),
+        };
+
+        // 3. 发送 RECV 请求
+        match sync_mode.protocol {
+            SyncProtocol::V1 => {
+                // ID_RECV + 长度 + 远程路径
+                self.send_sync_request(&mut stream, b"RECV", remote_path).await?;
+            }
+            SyncProtocol::V2 => {
+                // ID_RCV2 + 长度 + 远程路径，随后是 ID_RCV2 + 压缩标志
+                self.send_sync_request(&mut stream, b"RCV2", remote_path).await?;
+                stream.write_all(b"RCV2").await?;
+                stream.write_u32_le(sync_mode.compression.flag()).await?;
+            }
+        }
+
+        let file = std::fs::File::create(local_path)
+            .with_context(|| format!("无法创建本地文件: {}", local_path))?;
+
+        // 4. 循环接收 DATA 数据块，直到 DONE (解压和写入在后台线程中进行)
+        let (chunks, written, decoder) = adb_compress::spawn_decoder(file, sync_mode.compression);
+        let received = self.recv_data(&mut stream, chunks, &written, file_size, callback.as_ref()).await;
+        let decoded = decoder.await.context("解压线程异常退出")?.context("写入本地文件失败");
+
+        match received.and_then(|wire_bytes| decoded.map(|_| wire_bytes)) {
+            Ok(wire_bytes) => {
+                let total = written.load(Ordering::Relaxed);
+                if let Some(cb) = &callback {
+                    cb(total, file_size);
+                }
+                Ok(TransferStats::new(sync_mode, total, wire_bytes, started))
+            }
+            Err(e) => {
+                // 传输失败时删除不完整的文件
+                let _ = fs::remove_file(local_path).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// 接收 RECV/RCV2 返回的 DATA/DONE/FAIL 帧并交给解压线程，返回实际接收的 DATA 字节数
+    async fn recv_data(
+        &self,
+        stream: &mut AdbStream,
+        chunks: mpsc::Sender<Vec<u8>>,
+        written: &AtomicU64,
+        file_size: u64,
+        callback: Option<&ProgressCallback>,
+    ) -> Result<u64> {
+        let mut wire_bytes = 0u64;
+
+        loop {
+            let mut id = [0u8; 4];
+            tokio::select! {
+                read = stream.read_exact(&mut id) => { read?; }
+                _ = self.cancel.cancelled() => return Err(TransferCancelled.into()),
+            }
+
+            match &id {
+                b"DATA" => {
+                    let len = stream.read_u32_le().await? as usize;
+                    if len > adb_compress::MAX_CHUNK {
+                        bail!("DATA 块过大: {} 字节", len);
+                    }
+                    let mut chunk = vec![0u8; len];
+                    stream.read_exact(&mut chunk).await?;
+                    if chunks.send(chunk).await.is_err() {
+                        // 解压线程已退出，具体错误由线程结果给出
+                        bail!("写入本地文件中断");
+                    }
+
+                    // === 触发进度回调 ===
+                    wire_bytes += len as u64;
+                    if let Some(cb) = callback {
+                        cb(written.load(Ordering::Relaxed), file_size);
+                    }
+                }
+                b"DONE" => {
+                    // DONE 后跟 4 字节占位
+                    let _ = stream.read_u32_le().await?;
+                    return Ok(wire_bytes);
+                }
+                b"FAIL" => {
+                    let msg = self.read_sync_fail(stream).await?;
+                    bail!("拉取失败: {}", msg);
+                }
+                other => bail!("未知的 SYNC 响应: {}", String::from_utf8_lossy(other)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/sdcard/a b"), "'/sdcard/a b'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_parse_stat_v2() {
+        let mut buf = [0u8; 68];
+        buf[20..24].copy_from_slice(&(S_IFDIR | 0o755).to_le_bytes());
+        buf[36..44].copy_from_slice(&(5u64 << 32).to_le_bytes());
+        buf[52..60].copy_from_slice(&1_700_000_000u64.to_le_bytes());
+
+        let stat = parse_stat_v2(&buf);
+        assert_eq!(stat.error, 0);
+        assert_eq!(stat.size, 5u64 << 32);
+        assert_eq!(stat.mtime, 1_700_000_000);
+
+        let entry = RemoteEntry::new("DCIM".to_string(), stat.mode, stat.size, stat.mtime);
+        assert!(entry.is_dir);
+        assert!(!entry.is_symlink);
+    }
+
+    #[test]
+    fn test_file_meta_mode_override() {
+        let path = std::env::temp_dir().join(format!("adb_sync_meta_{}", std::process::id()));
+        std::fs::write(&path, b"#!/bin/sh").unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+
+        let meta = FileMeta::from_metadata(&metadata, Some(0o755));
+        assert_eq!(meta.mode, 0o100755);
+        assert!(meta.mtime > 0);
+
+        let meta = FileMeta::from_metadata(&metadata, None);
+        assert_eq!(meta.mode & S_IFMT, S_IFREG);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_parse_features() {
+        assert_eq!(parse_features("shell_v2,cmd, stat_v2,,ls_v2"), vec!["shell_v2", "cmd", "stat_v2", "ls_v2"]);
+    }
+
+    #[test]
+    fn test_collect_push_entries() {
+        let root = std::env::temp_dir().join(format!("adb_sync_walk_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(root.join("empty")).unwrap();
+        std::fs::write(root.join("a.txt"), b"hello").unwrap();
+        std::fs::write(root.join("sub").join("b.bin"), b"12").unwrap();
+
+        let plan = collect_push_entries(&root).unwrap();
+        assert_eq!(plan.files.iter().map(|f| f.size).sum::<u64>(), 7);
+
+        // 推送到 /sdcard/Download/ 时目标目录为 /sdcard/Download/<目录名>，目录名只出现一次
+        let name = root.file_name().unwrap().to_string_lossy().to_string();
+        let remote_dir = format!("/sdcard/Download/{}", name);
+        let files: Vec<String> = plan.files.iter().map(|f| remote_join(&remote_dir, &f.relative)).collect();
+        assert_eq!(files, vec![format!("{}/a.txt", remote_dir), format!("{}/sub/b.bin", remote_dir)]);
+        let dirs: Vec<String> = plan.empty_dirs.iter().map(|d| remote_join(&remote_dir, d)).collect();
+        assert_eq!(dirs, vec![format!("{}/empty", remote_dir)]);
+
+        // 空目录本身
+        let empty = collect_push_entries(&root.join("empty")).unwrap();
+        assert!(empty.files.is_empty());
+        assert_eq!(empty.empty_dirs, vec![String::new()]);
+        assert_eq!(remote_join("/sdcard/empty", &empty.empty_dirs[0]), "/sdcard/empty");
+
+        let _ = std::fs::remove_dir_all(&root);
     }
 }
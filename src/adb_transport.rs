@@ -0,0 +1,674 @@
+//! Scrcpy Launcher - ADB 直连模块
+//!
+//! 不经过 ADB Server，直接通过 TCP 与设备上的 adbd 通信 (`adb tcpip` 打开的端口)：
+//! - 握手: CNXN，设备要求认证时用 `adb_key` 的当前密钥对 AUTH 令牌签名，签名未被接受则发送公钥等待用户确认
+//! - 数据流: OPEN / OKAY / WRTE / CLSE，每个服务 (shell、sync、exec) 是连接上的一个流
+//!
+//! 每台设备保持一条连接，读线程按流 ID 分发设备消息，写线程串行发送。
+//! 流同时实现阻塞的 `Read`/`Write` (供 `adb_shell` 使用) 和 tokio 的
+//! `AsyncRead`/`AsyncWrite` (供 `adb_sync` 使用)。
+//!
+//! 是否直连由 `Config.device_backends` 按设备设置，默认仍使用 ADB Server。
+//! `probe` 只做一次握手，供局域网扫描确认端口上是 adbd 并读取设备型号。
+//! Android 11+ 无线调试端口要求 TLS (STLS)，这类设备仍需通过 ADB Server 连接。
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+use anyhow::{Result, bail, Context};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::sync::mpsc as async_mpsc;
+use crate::adb_key::AdbKey;
+use crate::adb_sync::parse_features;
+use crate::config::Config;
+
+const A_CNXN: u32 = 0x4e584e43;
+const A_AUTH: u32 = 0x48545541;
+const A_OPEN: u32 = 0x4e45504f;
+const A_OKAY: u32 = 0x59414b4f;
+const A_CLSE: u32 = 0x45534c43;
+const A_WRTE: u32 = 0x45545257;
+const A_STLS: u32 = 0x534c5453;
+
+/// 协议版本 (0x01000001 起不再校验 checksum)
+const A_VERSION: u32 = 0x01000001;
+/// 本端可接收的最大数据长度
+const MAX_PAYLOAD: u32 = 256 * 1024;
+/// 消息头长度
+const HEADER_SIZE: usize = 24;
+
+/// AUTH 消息类型
+const AUTH_TOKEN: u32 = 1;
+const AUTH_SIGNATURE: u32 = 2;
+const AUTH_RSAPUBLICKEY: u32 = 3;
+
+/// 向设备声明的特性 (与 ADB Server 支持的功能一致)
+const HOST_FEATURES: &str = "shell_v2,cmd,stat_v2,ls_v2,fixed_push_mkdir,apex,abb,abb_exec,\
+fixed_push_symlink_timestamp,remount_shell,sendrecv_v2,sendrecv_v2_brotli,sendrecv_v2_lz4,sendrecv_v2_zstd";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 发送公钥后等待用户在设备上确认的时间
+const AUTH_CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 设备的连接方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdbBackend {
+    /// 通过本机 ADB Server
+    #[default]
+    Server,
+    /// 直接连接设备的 adbd (序列号需为 ip:port)
+    Direct,
+}
+
+/// 异步读写的设备服务连接 (ADB Server 转发的 TCP 连接或直连的流)
+pub trait AsyncStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncStream for T {}
+
+pub type AdbStream = Box<dyn AsyncStream>;
+
+/// 设备使用的连接方式 (未指定设备时使用 ADB Server)
+pub fn backend_for(serial: Option<&str>) -> AdbBackend {
+    serial
+        .and_then(|s| Config::load().device_backends.get(s).copied())
+        .unwrap_or_default()
+}
+
+/// 协议消息
+#[derive(Debug, PartialEq)]
+struct Message {
+    command: u32,
+    arg0: u32,
+    arg1: u32,
+    data: Vec<u8>,
+}
+
+impl Message {
+    fn new(command: u32, arg0: u32, arg1: u32, data: Vec<u8>) -> Self {
+        Self { command, arg0, arg1, data }
+    }
+
+    /// 编码为 24 字节小端消息头 + 数据
+    fn encode(&self) -> Vec<u8> {
+        let checksum = self.data.iter().fold(0u32, |sum, &b| sum.wrapping_add(b as u32));
+        let mut buf = Vec::with_capacity(HEADER_SIZE + self.data.len());
+        for field in [self.command, self.arg0, self.arg1, self.data.len() as u32, checksum, !self.command] {
+            buf.extend_from_slice(&field.to_le_bytes());
+        }
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let field = |i: usize| u32::from_le_bytes([header[i * 4], header[i * 4 + 1], header[i * 4 + 2], header[i * 4 + 3]]);
+
+        let command = field(0);
+        if field(5) != !command {
+            bail!("无效的 ADB 消息头");
+        }
+        let len = field(3) as usize;
+        if len > 16 * 1024 * 1024 {
+            bail!("ADB 消息过长: {} 字节", len);
+        }
+        let mut data = vec![0u8; len];
+        reader.read_exact(&mut data)?;
+        Ok(Self::new(command, field(1), field(2), data))
+    }
+}
+
+/// 设备在 CNXN 中发送的身份信息
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct DeviceBanner {
+    pub product: Option<String>,
+    pub model: Option<String>,
+    pub device: Option<String>,
+    pub features: Vec<String>,
+}
+
+impl DeviceBanner {
+    /// 解析 `device::ro.product.name=x;ro.product.model=y;ro.product.device=z;features=a,b`
+    pub fn parse(banner: &str) -> Self {
+        let props = banner.split_once("::").map(|(_, p)| p).unwrap_or("");
+        let mut result = Self::default();
+        for prop in props.split(';') {
+            let Some((key, value)) = prop.trim_end_matches('\0').split_once('=') else {
+                continue;
+            };
+            match key {
+                "ro.product.name" => result.product = Some(value.to_string()),
+                "ro.product.model" => result.model = Some(value.to_string()),
+                "ro.product.device" => result.device = Some(value.to_string()),
+                "features" => result.features = parse_features(value),
+                _ => {}
+            }
+        }
+        result
+    }
+}
+
+/// 握手探测结果
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ProbeResult {
+    /// 设备接受了连接 (无需认证或已信任当前密钥)
+    Device(DeviceBanner),
+    /// 设备要求认证且不信任当前密钥
+    AuthRequired,
+    /// 设备要求 TLS (无线调试端口)
+    TlsRequired,
+}
+
+/// 异步读取一条消息
+async fn read_message_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Message> {
+    let mut buf = vec![0u8; HEADER_SIZE];
+    reader.read_exact(&mut buf).await?;
+    let len = u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]);
+    if len > MAX_PAYLOAD {
+        bail!("ADB 消息过长: {} 字节", len);
+    }
+    buf.resize(HEADER_SIZE + len as usize, 0);
+    reader.read_exact(&mut buf[HEADER_SIZE..]).await?;
+    Message::read(&mut buf.as_slice())
+}
+
+/// 发送 CNXN 并读取设备应答，用于确认端口上确实是 adbd 并获取设备身份
+///
+/// 设备要求认证时用 `key` 签名一次：签名被接受则得到 CNXN，否则视为需要授权。
+/// 不是 ADB 协议的应答 (消息头校验失败) 返回错误。调用方负责设置超时。
+pub async fn probe<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, key: Option<&AdbKey>) -> Result<ProbeResult> {
+    let banner = format!("host::features={}", HOST_FEATURES);
+    stream.write_all(&Message::new(A_CNXN, A_VERSION, MAX_PAYLOAD, banner.into_bytes()).encode()).await?;
+
+    let mut signed = false;
+    loop {
+        let message = read_message_async(stream).await?;
+        match message.command {
+            A_CNXN => return Ok(ProbeResult::Device(DeviceBanner::parse(&String::from_utf8_lossy(&message.data)))),
+            A_STLS => return Ok(ProbeResult::TlsRequired),
+            A_AUTH if message.arg0 == AUTH_TOKEN && !signed => {
+                let Some(key) = key else {
+                    return Ok(ProbeResult::AuthRequired);
+                };
+                let signature = key.sign(&message.data)?;
+                stream.write_all(&Message::new(A_AUTH, AUTH_SIGNATURE, 0, signature).encode()).await?;
+                signed = true;
+            }
+            A_AUTH => return Ok(ProbeResult::AuthRequired),
+            command => bail!("意外的 ADB 消息: {:#x}", command),
+        }
+    }
+}
+
+/// 读线程分发给流的事件
+enum Event {
+    /// 打开成功 (携带设备端流 ID) 或上一次写入已被确认
+    Okay(u32),
+    Data(Vec<u8>),
+    Close,
+}
+
+/// 连接的共享状态
+struct Shared {
+    outgoing: mpsc::Sender<Vec<u8>>,
+    streams: Mutex<HashMap<u32, async_mpsc::UnboundedSender<Event>>>,
+    next_id: AtomicU32,
+    closed: AtomicBool,
+    /// 单个 WRTE 的最大数据长度 (双方较小值)
+    max_payload: usize,
+}
+
+impl Shared {
+    fn send(&self, message: Message) {
+        let _ = self.outgoing.send(message.encode());
+    }
+}
+
+/// 与一台设备的直连
+pub struct DirectConnection {
+    shared: Arc<Shared>,
+    socket: TcpStream,
+    banner: DeviceBanner,
+}
+
+impl DirectConnection {
+    /// 连接设备并完成握手
+    pub fn connect(addr: &str, key: &AdbKey) -> Result<Self> {
+        let socket_addr = addr
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut a| a.next())
+            .with_context(|| format!("无效的设备地址: {}", addr))?;
+        let mut socket = TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT)
+            .with_context(|| format!("无法连接到设备: {}", addr))?;
+        socket.set_nodelay(true)?;
+        socket.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+        let banner = format!("host::features={}", HOST_FEATURES);
+        socket.write_all(&Message::new(A_CNXN, A_VERSION, MAX_PAYLOAD, banner.into_bytes()).encode())?;
+        let cnxn = handshake(&mut socket, key)?;
+        socket.set_read_timeout(None)?;
+
+        let (outgoing, outgoing_rx) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            outgoing,
+            streams: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+            closed: AtomicBool::new(false),
+            max_payload: cnxn.arg1.clamp(4096, MAX_PAYLOAD) as usize,
+        });
+
+        let reader = socket.try_clone()?;
+        let writer = socket.try_clone()?;
+        let reader_shared = shared.clone();
+        std::thread::spawn(move || read_loop(reader, reader_shared));
+        std::thread::spawn(move || write_loop(writer, outgoing_rx));
+
+        Ok(Self {
+            shared,
+            socket,
+            banner: DeviceBanner::parse(&String::from_utf8_lossy(&cnxn.data)),
+        })
+    }
+
+    /// 设备的身份和特性
+    pub fn banner(&self) -> &DeviceBanner {
+        &self.banner
+    }
+
+    /// 连接是否已断开
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::SeqCst)
+    }
+
+    /// 发送 OPEN，返回尚未确认的流
+    fn start_open(&self, service: &str) -> Result<DirectStream> {
+        if self.is_closed() {
+            bail!("与设备的连接已断开");
+        }
+        let local_id = self.shared.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = async_mpsc::unbounded_channel();
+        self.shared.streams.lock().unwrap().insert(local_id, tx);
+
+        let mut data = service.as_bytes().to_vec();
+        data.push(0);
+        self.shared.send(Message::new(A_OPEN, local_id, 0, data));
+
+        Ok(DirectStream {
+            shared: self.shared.clone(),
+            local_id,
+            remote_id: 0,
+            rx,
+            buffer: VecDeque::new(),
+            write_pending: false,
+            eof: false,
+        })
+    }
+
+    /// 打开服务 (如 `shell,v2,raw:ls`)，阻塞等待设备确认
+    pub fn open(&self, service: &str) -> Result<DirectStream> {
+        let mut stream = self.start_open(service)?;
+        let event = stream.rx.blocking_recv();
+        stream.opened(event, service)?;
+        Ok(stream)
+    }
+
+    /// 打开服务，异步等待设备确认
+    pub async fn open_async(&self, service: &str) -> Result<DirectStream> {
+        let mut stream = self.start_open(service)?;
+        let event = stream.rx.recv().await;
+        stream.opened(event, service)?;
+        Ok(stream)
+    }
+}
+
+impl Drop for DirectConnection {
+    fn drop(&mut self) {
+        // 关闭 socket 使读写线程退出
+        let _ = self.socket.shutdown(Shutdown::Both);
+    }
+}
+
+/// 完成 CNXN/AUTH 握手，返回设备的 CNXN 消息
+fn handshake(socket: &mut TcpStream, key: &AdbKey) -> Result<Message> {
+    let mut signed = false;
+    loop {
+        let message = Message::read(socket).context("设备握手失败 (设备拒绝连接或未确认授权)")?;
+        match message.command {
+            A_CNXN => return Ok(message),
+            A_STLS => bail!("设备要求 TLS 连接 (无线调试端口)，请改用 ADB Server 连接"),
+            A_AUTH if message.arg0 == AUTH_TOKEN && !signed => {
+                let signature = key.sign(&message.data)?;
+                socket.write_all(&Message::new(A_AUTH, AUTH_SIGNATURE, 0, signature).encode())?;
+                signed = true;
+            }
+            A_AUTH if message.arg0 == AUTH_TOKEN => {
+                // 设备不认识此密钥：发送公钥，由用户在设备上确认
+                let mut data = key.public_key().as_bytes().to_vec();
+                data.push(0);
+                socket.write_all(&Message::new(A_AUTH, AUTH_RSAPUBLICKEY, 0, data).encode())?;
+                socket.set_read_timeout(Some(AUTH_CONFIRM_TIMEOUT))?;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 读取设备消息并分发到各个流，连接断开后关闭所有流
+fn read_loop(mut reader: TcpStream, shared: Arc<Shared>) {
+    while let Ok(message) = Message::read(&mut reader) {
+        let (remote_id, local_id) = (message.arg0, message.arg1);
+        let mut streams = shared.streams.lock().unwrap();
+        let Some(tx) = streams.get(&local_id) else {
+            // 本端已关闭的流
+            if message.command == A_WRTE {
+                shared.send(Message::new(A_CLSE, local_id, remote_id, Vec::new()));
+            }
+            continue;
+        };
+
+        match message.command {
+            A_OKAY => {
+                let _ = tx.send(Event::Okay(remote_id));
+            }
+            A_WRTE => {
+                let _ = tx.send(Event::Data(message.data));
+                shared.send(Message::new(A_OKAY, local_id, remote_id, Vec::new()));
+            }
+            A_CLSE => {
+                let _ = tx.send(Event::Close);
+                streams.remove(&local_id);
+            }
+            _ => {}
+        }
+    }
+
+    shared.closed.store(true, Ordering::SeqCst);
+    // 丢弃发送端，等待中的流会读到连接结束
+    shared.streams.lock().unwrap().clear();
+}
+
+/// 串行写出消息
+fn write_loop(mut writer: TcpStream, outgoing: mpsc::Receiver<Vec<u8>>) {
+    for data in outgoing {
+        if writer.write_all(&data).is_err() {
+            break;
+        }
+    }
+    let _ = writer.shutdown(Shutdown::Both);
+}
+
+/// 直连设备上的一个服务流
+///
+/// 每次写入后需要等设备 OKAY 才能发送下一个 WRTE，期间收到的数据先缓存。
+pub struct DirectStream {
+    shared: Arc<Shared>,
+    local_id: u32,
+    remote_id: u32,
+    rx: async_mpsc::UnboundedReceiver<Event>,
+    buffer: VecDeque<u8>,
+    write_pending: bool,
+    eof: bool,
+}
+
+impl DirectStream {
+    /// 处理 OPEN 的应答
+    fn opened(&mut self, event: Option<Event>, service: &str) -> Result<()> {
+        match event {
+            Some(Event::Okay(remote_id)) => {
+                self.remote_id = remote_id;
+                Ok(())
+            }
+            _ => {
+                self.eof = true;
+                bail!("设备拒绝服务: {}", service)
+            }
+        }
+    }
+
+    fn handle(&mut self, event: Option<Event>) {
+        match event {
+            Some(Event::Okay(_)) => self.write_pending = false,
+            Some(Event::Data(data)) => self.buffer.extend(data),
+            Some(Event::Close) | None => self.eof = true,
+        }
+    }
+
+    /// 从缓存中取出数据
+    fn take(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.buffer.len());
+        for (dst, src) in buf.iter_mut().zip(self.buffer.drain(..n)) {
+            *dst = src;
+        }
+        n
+    }
+
+    /// 发送一个 WRTE，返回写入的字节数
+    fn send(&mut self, buf: &[u8]) -> usize {
+        let n = buf.len().min(self.shared.max_payload);
+        self.shared.send(Message::new(A_WRTE, self.local_id, self.remote_id, buf[..n].to_vec()));
+        self.write_pending = true;
+        n
+    }
+}
+
+fn broken_pipe() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "设备已关闭数据流")
+}
+
+impl Read for DirectStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.buffer.is_empty() && !self.eof {
+            let event = self.rx.blocking_recv();
+            self.handle(event);
+        }
+        Ok(self.take(buf))
+    }
+}
+
+impl Write for DirectStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        while self.write_pending && !self.eof {
+            let event = self.rx.blocking_recv();
+            self.handle(event);
+        }
+        if self.eof {
+            return Err(broken_pipe());
+        }
+        Ok(self.send(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncRead for DirectStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if !this.buffer.is_empty() || this.eof {
+                let n = this.take(buf.initialize_unfilled());
+                buf.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            match this.rx.poll_recv(cx) {
+                Poll::Ready(event) => this.handle(event),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for DirectStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            if this.eof {
+                return Poll::Ready(Err(broken_pipe()));
+            }
+            if !this.write_pending {
+                return Poll::Ready(Ok(this.send(buf)));
+            }
+            match this.rx.poll_recv(cx) {
+                Poll::Ready(event) => this.handle(event),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for DirectStream {
+    fn drop(&mut self) {
+        if self.shared.streams.lock().unwrap().remove(&self.local_id).is_some() && self.remote_id != 0 {
+            self.shared.send(Message::new(A_CLSE, self.local_id, self.remote_id, Vec::new()));
+        }
+    }
+}
+
+/// 一台设备的直连，单独加锁
+type ConnectionSlot = Arc<Mutex<Option<Arc<DirectConnection>>>>;
+
+/// 已建立的直连 (序列号 -> 连接)
+static CONNECTIONS: OnceLock<Mutex<HashMap<String, ConnectionSlot>>> = OnceLock::new();
+
+/// 获取设备的直连，没有或已断开时用当前密钥 (见 `AdbKey::load_active`) 重新连接
+///
+/// 连接过程只持有该设备的锁：同一设备的并发请求会等待并复用同一条连接，
+/// 其他设备不受影响 (握手可能要等用户在设备上确认)。
+pub fn connection(serial: &str) -> Result<Arc<DirectConnection>> {
+    let slot = CONNECTIONS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap()
+        .entry(serial.to_string())
+        .or_default()
+        .clone();
+
+    let mut slot = slot.lock().unwrap();
+    if let Some(conn) = slot.as_ref().filter(|c| !c.is_closed()) {
+        return Ok(conn.clone());
+    }
+
+    let key = AdbKey::load_active()?;
+    let conn = Arc::new(DirectConnection::connect(serial, &key)?);
+    *slot = Some(conn.clone());
+    Ok(conn)
+}
+
+/// 在阻塞线程中获取设备的直连
+pub async fn connection_async(serial: &str) -> Result<Arc<DirectConnection>> {
+    let serial = serial.to_string();
+    tokio::task::spawn_blocking(move || connection(&serial)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use rsa::{Pkcs1v15Sign, RsaPrivateKey};
+    use sha1::Sha1;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// 模拟设备：要求签名认证，`shell:echo` 返回参数，`sync:` 把收到的数据原样返回
+    fn stand_in_device(key: &RsaPrivateKey) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let public = key.to_public_key();
+
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let send = |socket: &mut TcpStream, m: Message| socket.write_all(&m.encode()).unwrap();
+
+            let cnxn = Message::read(&mut socket).unwrap();
+            assert_eq!(cnxn.command, A_CNXN);
+            let token = [7u8; 20];
+            send(&mut socket, Message::new(A_AUTH, AUTH_TOKEN, 0, token.to_vec()));
+            let auth = Message::read(&mut socket).unwrap();
+            assert_eq!(auth.arg0, AUTH_SIGNATURE);
+            public.verify(Pkcs1v15Sign::new::<Sha1>(), &token, &auth.data).unwrap();
+            let banner = b"device::ro.product.name=sdk;ro.product.model=Pixel_7;ro.product.device=emu;features=shell_v2,cmd";
+            send(&mut socket, Message::new(A_CNXN, A_VERSION, 4096, banner.to_vec()));
+
+            let mut remote_id = 100;
+            while let Ok(m) = Message::read(&mut socket) {
+                match m.command {
+                    A_OPEN => {
+                        remote_id += 1;
+                        let service = String::from_utf8_lossy(&m.data).trim_end_matches('\0').to_string();
+                        send(&mut socket, Message::new(A_OKAY, remote_id, m.arg0, Vec::new()));
+                        if let Some(text) = service.strip_prefix("shell:echo ") {
+                            send(&mut socket, Message::new(A_WRTE, remote_id, m.arg0, text.as_bytes().to_vec()));
+                            send(&mut socket, Message::new(A_CLSE, remote_id, m.arg0, Vec::new()));
+                        }
+                    }
+                    A_WRTE => {
+                        send(&mut socket, Message::new(A_OKAY, m.arg1, m.arg0, Vec::new()));
+                        send(&mut socket, Message::new(A_WRTE, m.arg1, m.arg0, m.data));
+                    }
+                    _ => {}
+                }
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn test_direct_connection() {
+        let private = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 1024).unwrap();
+        let addr = stand_in_device(&private);
+        let conn = DirectConnection::connect(&addr, &AdbKey::from_private_key(private)).unwrap();
+        assert_eq!(conn.banner().model.as_deref(), Some("Pixel_7"));
+        assert_eq!(conn.banner().features, vec!["shell_v2", "cmd"]);
+
+        let mut output = String::new();
+        Read::read_to_string(&mut conn.open("shell:echo hello").unwrap(), &mut output).unwrap();
+        assert_eq!(output, "hello");
+
+        // 单个 WRTE 不超过设备的 max payload (4096)
+        let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let echoed = runtime.block_on(async {
+            let mut stream = conn.open_async("sync:").await.unwrap();
+            AsyncWriteExt::write_all(&mut stream, &data).await.unwrap();
+            let mut echoed = vec![0u8; data.len()];
+            AsyncReadExt::read_exact(&mut stream, &mut echoed).await.unwrap();
+            echoed
+        });
+        assert_eq!(echoed, data);
+    }
+
+    #[test]
+    fn test_probe() {
+        let private = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 1024).unwrap();
+        let addr = stand_in_device(&private);
+        let key = AdbKey::from_private_key(private);
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let result = runtime.block_on(async {
+            let mut stream = tokio::net::TcpStream::connect(&addr).await.unwrap();
+            probe(&mut stream, Some(&key)).await.unwrap()
+        });
+        let ProbeResult::Device(banner) = result else {
+            panic!("unexpected probe result: {:?}", result);
+        };
+        assert_eq!(banner.model.as_deref(), Some("Pixel_7"));
+        assert_eq!(banner.product.as_deref(), Some("sdk"));
+    }
+}
@@ -0,0 +1,294 @@
+//! Scrcpy Launcher - 分包 APK 模块
+//!
+//! 读取 `.apks` (bundletool)、`.xapk`、`.apkm` 压缩包，
+//! 按设备的 ABI、屏幕密度和语言选出需要安装的分包。
+//!
+//! 分包命名约定：
+//! - `.apks`: `splits/base-master.apk`、`splits/base-arm64_v8a.apk`、`splits/base-xxhdpi.apk`、`splits/base-zh.apk`
+//! - `.xapk`: `com.example.apk`、`config.arm64_v8a.apk`、`config.xxhdpi.apk`、`config.zh.apk`
+//! - `.apkm`: `base.apk`、`split_config.arm64_v8a.apk`、`split_config.xxhdpi.apk`、`split_config.zh.apk`
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, bail, Context};
+use serde::Serialize;
+
+/// 支持的分包压缩包扩展名
+pub const BUNDLE_EXTENSIONS: [&str; 3] = ["apks", "xapk", "apkm"];
+
+const ABIS: [&str; 7] = ["armeabi", "armeabi_v7a", "arm64_v8a", "x86", "x86_64", "mips", "mips64"];
+
+/// 密度分包名称及对应的 dpi
+const DENSITIES: [(&str, u32); 7] = [
+    ("ldpi", 120),
+    ("mdpi", 160),
+    ("tvdpi", 213),
+    ("hdpi", 240),
+    ("xhdpi", 320),
+    ("xxhdpi", 480),
+    ("xxxhdpi", 640),
+];
+
+/// 分包类型
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum SplitKind {
+    /// 基础包或功能模块，始终安装
+    Required,
+    Abi(String),
+    Density(String),
+    Language(String),
+}
+
+/// 压缩包中的一个 APK
+#[derive(Serialize, Clone, Debug)]
+pub struct SplitApk {
+    /// 压缩包内的路径
+    pub entry: String,
+    /// 分包名 (去掉目录和扩展名)
+    pub name: String,
+    pub kind: SplitKind,
+    pub size: u64,
+}
+
+/// 判断文件是否为分包压缩包
+pub fn is_bundle(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| BUNDLE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// 根据压缩包内的路径和分包名判断类型
+fn classify(entry: &str, name: &str) -> SplitKind {
+    // bundletool 的分包为 splits/<模块>-<配置>，如 base-arm64_v8a、feature-xxhdpi；
+    // 其他位置的文件名可能本身带 `-` (如 my-app.apk)，不按这个规则拆分
+    let config = name
+        .strip_prefix("split_config.")
+        .or_else(|| name.strip_prefix("config."))
+        .or_else(|| {
+            entry
+                .starts_with("splits/")
+                .then(|| name.rsplit_once('-').map(|(_, c)| c))
+                .flatten()
+        });
+    let Some(config) = config else {
+        return SplitKind::Required;
+    };
+
+    if ABIS.contains(&config) {
+        SplitKind::Abi(config.to_string())
+    } else if DENSITIES.iter().any(|(d, _)| *d == config) {
+        SplitKind::Density(config.to_string())
+    } else if (2..=3).contains(&config.len()) && config.chars().all(|c| c.is_ascii_lowercase()) {
+        SplitKind::Language(config.to_string())
+    } else {
+        // <模块>-master 以及无法识别的配置分包
+        SplitKind::Required
+    }
+}
+
+/// 列出压缩包中的 APK (忽略 bundletool 的 standalones 目录和 OBB 等其他文件)
+pub fn list_splits(bundle_path: &str) -> Result<Vec<SplitApk>> {
+    let file = File::open(bundle_path).with_context(|| format!("无法打开文件: {}", bundle_path))?;
+    let mut archive = zip::ZipArchive::new(file).context("无效的分包压缩包")?;
+
+    let mut splits = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        let path = entry.name().to_string();
+        if !path.to_lowercase().ends_with(".apk") || path.starts_with("standalones/") {
+            continue;
+        }
+        let name = path.rsplit('/').next().unwrap_or(&path).trim_end_matches(".apk").to_string();
+        splits.push(SplitApk {
+            kind: classify(&path, &name),
+            entry: path,
+            name,
+            size: entry.size(),
+        });
+    }
+
+    if splits.is_empty() {
+        bail!("压缩包中没有 APK");
+    }
+    Ok(splits)
+}
+
+/// 设备配置 (来自 getprop)
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct DeviceSpec {
+    /// 按优先级排列的 ABI，如 arm64_v8a, armeabi_v7a
+    pub abis: Vec<String>,
+    pub density: u32,
+    /// 语言代码，如 zh
+    pub language: Option<String>,
+}
+
+impl DeviceSpec {
+    /// 获取设备配置的 shell 命令，输出依次为 ABI 列表、密度、语言
+    pub const GETPROP_COMMAND: &'static str =
+        "getprop ro.product.cpu.abilist; getprop ro.sf.lcd_density; getprop persist.sys.locale; getprop ro.product.locale";
+
+    /// 解析 `GETPROP_COMMAND` 的输出
+    pub fn parse(output: &str) -> Self {
+        let lines: Vec<&str> = output.lines().map(str::trim).collect();
+        let line = |i: usize| lines.get(i).copied().unwrap_or("");
+
+        let abis = line(0)
+            .split(',')
+            .map(|a| a.trim().replace('-', "_"))
+            .filter(|a| !a.is_empty())
+            .collect();
+        let density = line(1).parse().unwrap_or(0);
+        let language = [line(2), line(3)]
+            .into_iter()
+            .find(|l| !l.is_empty())
+            .and_then(|l| l.split(['-', '_']).next())
+            .map(|l| l.to_lowercase());
+
+        Self { abis, density, language }
+    }
+}
+
+/// 选出需要安装的分包
+///
+/// 每一类配置各选一个值，所有模块中该值的分包都会安装：
+/// - 必需包全部安装
+/// - ABI：按设备 ABI 优先级选择第一个有分包的
+/// - 密度：选择不低于设备密度的最小值，没有时选最大的
+/// - 语言：与设备语言一致的 (没有则不安装，使用基础包中的默认资源)
+pub fn select_splits(splits: &[SplitApk], spec: &DeviceSpec) -> Result<Vec<SplitApk>> {
+    if !splits.iter().any(|s| s.kind == SplitKind::Required) {
+        bail!("压缩包中没有基础 APK");
+    }
+
+    let has_abi_splits = splits.iter().any(|s| matches!(s.kind, SplitKind::Abi(_)));
+    let abi = spec.abis.iter().find(|abi| splits.iter().any(|s| s.kind == SplitKind::Abi(abi.to_string())));
+    if has_abi_splits && abi.is_none() {
+        bail!("没有与设备 CPU 架构 ({}) 匹配的分包", spec.abis.join(", "));
+    }
+
+    let mut densities: Vec<(&str, u32)> = DENSITIES
+        .into_iter()
+        .filter(|(d, _)| splits.iter().any(|s| s.kind == SplitKind::Density(d.to_string())))
+        .collect();
+    densities.sort_by_key(|(_, v)| *v);
+    let density = densities
+        .iter()
+        .find(|(_, v)| *v >= spec.density)
+        .or(densities.last())
+        .map(|(d, _)| *d);
+
+    let wanted = |kind: &SplitKind| match kind {
+        SplitKind::Required => true,
+        SplitKind::Abi(a) => abi == Some(a),
+        SplitKind::Density(d) => density == Some(d.as_str()),
+        SplitKind::Language(l) => spec.language.as_ref() == Some(l),
+    };
+    Ok(splits.iter().filter(|s| wanted(&s.kind)).cloned().collect())
+}
+
+/// 把选中的分包解压到目录，返回本地路径 (与 `splits` 顺序一致)
+///
+/// 文件只按序号命名，压缩包里的条目名可能包含 `..\` 等路径，不能用来拼接本地路径。
+pub fn extract(bundle_path: &str, splits: &[SplitApk], dir: &Path) -> Result<Vec<PathBuf>> {
+    let file = File::open(bundle_path).with_context(|| format!("无法打开文件: {}", bundle_path))?;
+    let mut archive = zip::ZipArchive::new(file).context("无效的分包压缩包")?;
+    std::fs::create_dir_all(dir)?;
+
+    splits
+        .iter()
+        .enumerate()
+        .map(|(i, split)| {
+            let target = dir.join(format!("{}.apk", i));
+            let mut entry = archive.by_name(&split.entry)?;
+            let mut out = File::create(&target)?;
+            std::io::copy(&mut entry, &mut out)?;
+            Ok(target)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(name: &str) -> SplitApk {
+        let entry = format!("{}.apk", name);
+        SplitApk { kind: classify(&entry, name), entry, name: name.to_string(), size: 1 }
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("splits/base-master.apk", "base-master"), SplitKind::Required);
+        assert_eq!(classify("base.apk", "base"), SplitKind::Required);
+        assert_eq!(classify("com.example.app.apk", "com.example.app"), SplitKind::Required);
+        assert_eq!(classify("splits/base-arm64_v8a.apk", "base-arm64_v8a"), SplitKind::Abi("arm64_v8a".into()));
+        assert_eq!(classify("splits/camera-xxhdpi.apk", "camera-xxhdpi"), SplitKind::Density("xxhdpi".into()));
+        assert_eq!(classify("splits/base-zh.apk", "base-zh"), SplitKind::Language("zh".into()));
+        assert_eq!(classify("config.xxhdpi.apk", "config.xxhdpi"), SplitKind::Density("xxhdpi".into()));
+        assert_eq!(classify("split_config.zh.apk", "split_config.zh"), SplitKind::Language("zh".into()));
+        // 带 `-` 的基础包名不是语言分包
+        assert_eq!(classify("my-app.apk", "my-app"), SplitKind::Required);
+        assert_eq!(classify("com.example-new.apk", "com.example-new"), SplitKind::Required);
+        assert!(is_bundle("C:\\apps\\demo.XAPK"));
+        assert!(!is_bundle("demo.apk"));
+    }
+
+    #[test]
+    fn test_extract_stays_in_dir() {
+        use std::io::Write;
+
+        let root = std::env::temp_dir().join(format!("apk_bundle_extract_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let dir = root.join("out");
+        std::fs::create_dir_all(&root).unwrap();
+
+        // 条目名试图写到解压目录之外
+        let bundle = root.join("evil.apks");
+        let mut writer = zip::ZipWriter::new(File::create(&bundle).unwrap());
+        for name in ["base.apk", "..\\..\\evil.apk", "x\\..\\..\\foo.apk", "C:evil.apk"] {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let bundle = bundle.to_string_lossy().to_string();
+        let splits = list_splits(&bundle).unwrap();
+        assert_eq!(splits.len(), 4);
+        let files = extract(&bundle, &splits, &dir).unwrap();
+        for (i, file) in files.iter().enumerate() {
+            assert_eq!(file, &dir.join(format!("{}.apk", i)));
+        }
+        assert_eq!(std::fs::read_to_string(&files[1]).unwrap(), "..\\..\\evil.apk");
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 2);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_select_splits() {
+        let spec = DeviceSpec::parse("arm64-v8a,armeabi-v7a,armeabi\n440\nzh-Hans-CN\nen-US\n");
+        assert_eq!(spec.abis, vec!["arm64_v8a", "armeabi_v7a", "armeabi"]);
+        assert_eq!(spec.density, 440);
+        assert_eq!(spec.language.as_deref(), Some("zh"));
+
+        let splits: Vec<SplitApk> = [
+            "base", "split_config.armeabi_v7a", "split_config.arm64_v8a", "split_config.x86",
+            "split_config.hdpi", "split_config.xxhdpi", "split_config.xxxhdpi",
+            "split_config.en", "split_config.zh",
+        ].into_iter().map(split).collect();
+
+        let names: Vec<String> = select_splits(&splits, &spec).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["base", "split_config.arm64_v8a", "split_config.xxhdpi", "split_config.zh"]);
+
+        // 设备密度高于所有分包时选最大的；没有匹配的 ABI 时报错
+        let spec = DeviceSpec { abis: vec!["x86_64".into()], density: 700, language: None };
+        assert!(select_splits(&splits, &spec).is_err());
+        let spec = DeviceSpec { abis: vec!["x86".into()], ..spec };
+        let names: Vec<String> = select_splits(&splits, &spec).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["base", "split_config.x86", "split_config.xxxhdpi"]);
+    }
+}
@@ -0,0 +1,249 @@
+//! Scrcpy Launcher - 外部程序定位模块
+//!
+//! 按顺序查找 adb 和 scrcpy 可执行文件：
+//! 1. 配置中指定的路径 (`Config.adb_path` / `Config.scrcpy_path`)
+//! 2. 随程序打包的 `resources/bin`
+//! 3. `PATH` 中的同名程序
+//! 4. 环境变量 `ADB` / `SCRCPY` 指定的路径
+//!
+//! 文件名按平台添加可执行文件后缀 (Windows 为 `.exe`)。
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use crate::config::Config;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+/// Windows: CREATE_NO_WINDOW flag
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// scrcpy 版本缓存 (程序路径, 版本)，换用其它路径的程序时重新查询
+static SCRCPY_VERSION: Mutex<Option<(PathBuf, Option<String>)>> = Mutex::new(None);
+
+/// 需要定位的外部程序
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Tool {
+    Adb,
+    Scrcpy,
+}
+
+impl Tool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Adb => "adb",
+            Self::Scrcpy => "scrcpy",
+        }
+    }
+
+    /// 指定路径的环境变量
+    fn env_var(self) -> &'static str {
+        match self {
+            Self::Adb => "ADB",
+            Self::Scrcpy => "SCRCPY",
+        }
+    }
+
+    /// 带平台后缀的文件名，如 adb.exe
+    pub fn file_name(self) -> String {
+        format!("{}{}", self.name(), std::env::consts::EXE_SUFFIX)
+    }
+
+    /// 配置中指定的路径
+    fn configured_path(self, config: &Config) -> Option<&str> {
+        match self {
+            Self::Adb => config.adb_path.as_deref(),
+            Self::Scrcpy => config.scrcpy_path.as_deref(),
+        }
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    }
+}
+
+/// 找到程序的位置
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BinarySource {
+    /// 配置中指定
+    Config,
+    /// 随程序打包
+    Bundled,
+    /// PATH
+    Path,
+    /// 环境变量 ADB / SCRCPY
+    Env,
+}
+
+/// 定位结果
+#[derive(Serialize, Clone, Debug)]
+pub struct ResolvedBinary {
+    pub tool: Tool,
+    pub path: PathBuf,
+    pub source: BinarySource,
+    /// `--version` 报告的版本，无法获取时为空
+    pub version: Option<String>,
+}
+
+/// 获取资源目录路径
+pub fn resources_path() -> PathBuf {
+    // 在开发模式下使用相对路径，打包后使用资源目录
+    if cfg!(debug_assertions) {
+        PathBuf::from("resources")
+    } else {
+        // 获取可执行文件所在目录
+        std::env::current_exe()
+            .ok()
+            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+            .map(|p| p.join("resources"))
+            .unwrap_or_else(|| PathBuf::from("resources"))
+    }
+}
+
+/// 配置或环境变量中的路径可以是文件，也可以是所在目录
+fn existing_file(path: &Path, tool: Tool) -> Option<PathBuf> {
+    if path.is_dir() {
+        let file = path.join(tool.file_name());
+        return file.is_file().then_some(file);
+    }
+    path.is_file().then(|| path.to_path_buf())
+}
+
+/// 在 PATH 中查找
+fn find_in_path(tool: Tool) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(tool.file_name()))
+        .find(|file| file.is_file())
+}
+
+/// 按顺序查找程序，不运行它
+pub fn locate(tool: Tool, config: &Config) -> Option<(PathBuf, BinarySource)> {
+    if let Some(path) = tool.configured_path(config).and_then(|p| existing_file(Path::new(p), tool)) {
+        return Some((path, BinarySource::Config));
+    }
+    let bundled = resources_path().join("bin").join(tool.file_name());
+    if bundled.is_file() {
+        return Some((bundled, BinarySource::Bundled));
+    }
+    if let Some(path) = find_in_path(tool) {
+        return Some((path, BinarySource::Path));
+    }
+    std::env::var_os(tool.env_var())
+        .and_then(|p| existing_file(Path::new(&p), tool))
+        .map(|path| (path, BinarySource::Env))
+}
+
+/// 程序路径，找不到时返回文件名 (启动时由系统报告找不到程序)
+pub fn path(tool: Tool) -> PathBuf {
+    locate(tool, &Config::load())
+        .map(|(path, _)| path)
+        .unwrap_or_else(|| PathBuf::from(tool.file_name()))
+}
+
+/// 从 `--version` 输出中提取版本号
+///
+/// - adb: `Android Debug Bridge version 1.0.41` / `Version 35.0.2-12147458`，取后者
+/// - scrcpy: `scrcpy 2.4 <https://github.com/Genymobile/scrcpy>`
+pub fn parse_version(tool: Tool, output: &str) -> Option<String> {
+    let version = match tool {
+        Tool::Adb => output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("Version "))
+            .or_else(|| output.lines().next()?.split_whitespace().last()),
+        Tool::Scrcpy => output.lines().next()?.split_whitespace().nth(1),
+    };
+    version.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// 运行 `--version` 获取版本
+fn query_version(tool: Tool, path: &Path) -> Option<String> {
+    let mut cmd = Command::new(path);
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    let output = cmd
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    parse_version(tool, &String::from_utf8_lossy(&output.stdout))
+}
+
+/// 获取版本，scrcpy 的结果按路径缓存 (`refresh` 时重新查询)
+fn version_of(tool: Tool, path: &Path, refresh: bool) -> Option<String> {
+    if tool != Tool::Scrcpy {
+        return query_version(tool, path);
+    }
+    let mut cache = SCRCPY_VERSION.lock().unwrap();
+    match cache.as_ref() {
+        Some((cached, version)) if !refresh && cached == path => version.clone(),
+        _ => {
+            let version = query_version(tool, path);
+            *cache = Some((path.to_path_buf(), version.clone()));
+            version
+        }
+    }
+}
+
+/// 当前 scrcpy 的版本，同一程序只运行一次 `--version`
+pub fn scrcpy_version() -> Option<String> {
+    let (path, _) = locate(Tool::Scrcpy, &Config::load())?;
+    version_of(Tool::Scrcpy, &path, false)
+}
+
+/// 定位程序并获取版本 (重新查询，更新 scrcpy 版本缓存)
+pub fn resolve(tool: Tool) -> Result<ResolvedBinary> {
+    let Some((path, source)) = locate(tool, &Config::load()) else {
+        bail!(
+            "未找到 {}：请在设置中指定路径，或将 {} 放入 resources/bin、PATH，或设置环境变量 {}",
+            tool.name(),
+            tool.file_name(),
+            tool.env_var()
+        );
+    };
+    let version = version_of(tool, &path, true);
+    Ok(ResolvedBinary { tool, path, source, version })
+}
+
+/// 一个程序的检查结果
+#[derive(Serialize, Clone, Debug)]
+pub struct BinaryStatus {
+    pub tool: Tool,
+    pub binary: Option<ResolvedBinary>,
+    /// 找不到时的诊断信息
+    pub error: Option<String>,
+}
+
+/// 检查全部外部程序 (启动时和设置页使用)
+pub fn diagnose() -> Vec<BinaryStatus> {
+    [Tool::Adb, Tool::Scrcpy]
+        .into_iter()
+        .map(|tool| match resolve(tool) {
+            Ok(binary) => BinaryStatus { tool, binary: Some(binary), error: None },
+            Err(e) => BinaryStatus { tool, binary: None, error: Some(e.to_string()) },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        let adb = "Android Debug Bridge version 1.0.41\nVersion 35.0.2-12147458\nInstalled as /usr/bin/adb\n";
+        assert_eq!(parse_version(Tool::Adb, adb).as_deref(), Some("35.0.2-12147458"));
+        assert_eq!(parse_version(Tool::Adb, "Android Debug Bridge version 1.0.39\n").as_deref(), Some("1.0.39"));
+
+        let scrcpy = "scrcpy 2.4 <https://github.com/Genymobile/scrcpy>\n\nDependencies (compiled / linked):\n";
+        assert_eq!(parse_version(Tool::Scrcpy, scrcpy).as_deref(), Some("2.4"));
+        assert_eq!(parse_version(Tool::Scrcpy, ""), None);
+
+        assert!(Tool::Adb.file_name().starts_with("adb"));
+    }
+}
@@ -3,8 +3,22 @@
 //! 定义所有可以从前端调用的 Tauri 命令
 
 use crate::config::{Config, AppConfig, AppSettings, ScrcpyOptions};
-use crate::scrcpy::{self, AdbStatus, CommandResult, LaunchMode};
-use crate::adb_sync::AdbPusher;
+use crate::adb_host::{self, AdbDevice, AdbHost, AdbServer, LOCAL_SERVER};
+use crate::adb_shell::{AdbShell, ShellOutput};
+use crate::scrcpy::{self, AdbStatus, CommandResult, LaunchMode, LaunchPlan};
+use crate::adb_sync::{AdbPusher, ProgressCallback, RemoteEntry};
+use crate::adb_install::{AdbInstaller, InstallError, InstallMethod, InstallOptions, InstallReport};
+use crate::apk_bundle;
+use crate::adb_forward::{self, AdbForwarder, ForwardRule};
+use crate::adb_transport::{self, AdbBackend};
+use crate::adb_key::{self, AdbKeyInfo};
+use crate::lan_scan::{self, ScanOptions, ScanResult};
+use crate::binaries::{self, BinaryStatus, ResolvedBinary, Tool};
+use crate::session::{SessionInfo, SessionManager};
+use crate::scrcpy_args::{self, ArgsImport};
+use crate::scrcpy_compat::{self, CompatReport};
+use crate::transfer::{ProgressObserver, TransferInfo, TransferKind, TransferManager, TransferProgress, TransferRequest, TransferStatus};
+use std::sync::Arc;
 use tauri::{Emitter, Manager};
 use base64::prelude::*;
 use std::io::Read;
@@ -30,6 +44,221 @@ pub fn check_adb_status() -> AdbStatus {
     scrcpy::check_adb_status()
 }
 
+/// 获取已连接设备列表 (含状态、型号等信息)
+#[tauri::command]
+pub fn adb_devices() -> Result<Vec<AdbDevice>, String> {
+    scrcpy::list_devices()
+}
+
+/// 在设备上执行 shell 命令，返回 stdout/stderr 和退出代码
+#[tauri::command]
+pub async fn adb_shell(command: String, serial: Option<String>) -> Result<ShellOutput, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let serial = scrcpy::resolve_device(serial.as_deref())?;
+        AdbShell::new(Some(serial)).run(&command).map_err(|e| format!("{:#}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 设置当前设备 (连接多台设备时未指定序列号的操作都使用它)
+#[tauri::command]
+pub fn set_active_device(app: tauri::AppHandle, serial: Option<String>) {
+    scrcpy::set_active_device(serial.clone());
+    crate::tray::update_devices(&app, &scrcpy::list_devices().unwrap_or_default());
+    let _ = app.emit("active-device-changed", serial);
+}
+
+/// 列出设备的 forward / reverse 规则
+#[tauri::command]
+pub async fn forward_list(serial: Option<String>) -> Result<Vec<ForwardRule>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let serial = scrcpy::resolve_device(serial.as_deref())?;
+        AdbForwarder::new(serial).list().map_err(|e| format!("{:#}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 添加转发规则，`persist` (默认 true) 时保存到配置并在设备重新连接时自动应用
+#[tauri::command]
+pub async fn forward_add(rule: ForwardRule, persist: Option<bool>, serial: Option<String>) -> Result<ForwardRule, String> {
+    let serial = scrcpy::resolve_device_async(serial).await?;
+    let device = serial.clone();
+    let rule_clone = rule.clone();
+    let added = tauri::async_runtime::spawn_blocking(move || AdbForwarder::new(device).add(&rule_clone))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("{:#}", e))?;
+
+    // tcp:0 分配的端口每次不同，保存原规则
+    if persist.unwrap_or(true) {
+        let mut config = Config::load();
+        let rules = config.forward_rules.entry(serial).or_default();
+        rules.retain(|r| !(r.direction == rule.direction && r.local == rule.local && r.remote == rule.remote));
+        rules.push(rule);
+        config.save();
+    }
+    Ok(added)
+}
+
+/// 删除转发规则，同时从配置中移除 (`rule` 可以是 `forward_list` 返回的规则，对应保存的 tcp:0 规则)
+#[tauri::command]
+pub async fn forward_remove(rule: ForwardRule, serial: Option<String>) -> Result<CommandResult, String> {
+    let serial = scrcpy::resolve_device_async(serial).await?;
+    let mut config = Config::load();
+    if let Some(rules) = config.forward_rules.get_mut(&serial) {
+        if adb_forward::remove_saved(rules, &rule) {
+            if rules.is_empty() {
+                config.forward_rules.remove(&serial);
+            }
+            config.save();
+        }
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(move || AdbForwarder::new(serial).remove(&rule))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(match result {
+        Ok(()) => CommandResult { success: true, message: "已删除转发规则".to_string() },
+        Err(e) => CommandResult { success: false, message: format!("删除失败: {:#}", e) },
+    })
+}
+
+/// 设置设备的连接方式 (直连时先尝试连接设备，成功后才保存)
+#[tauri::command]
+pub async fn set_device_backend(serial: String, backend: AdbBackend) -> CommandResult {
+    let mut message = "已切换为通过 ADB Server 连接".to_string();
+    if backend == AdbBackend::Direct {
+        let device = serial.clone();
+        match tauri::async_runtime::spawn_blocking(move || adb_transport::connection(&device)).await {
+            Ok(Ok(conn)) => {
+                let name = conn.banner().model.clone().unwrap_or_else(|| serial.clone());
+                message = format!("已直连设备: {}", name);
+            }
+            Ok(Err(e)) => return CommandResult { success: false, message: format!("直连失败: {:#}", e) },
+            Err(e) => return CommandResult { success: false, message: e.to_string() },
+        }
+    }
+
+    let mut config = Config::load();
+    if backend == AdbBackend::Server {
+        config.device_backends.remove(&serial);
+    } else {
+        config.device_backends.insert(serial, backend);
+    }
+    config.save();
+    CommandResult { success: true, message }
+}
+
+/// 列出 adb 默认密钥和团队密钥 (含手机授权时显示的指纹)
+#[tauri::command]
+pub fn adb_key_list() -> Vec<AdbKeyInfo> {
+    adb_key::list_keys()
+}
+
+/// 生成团队密钥
+#[tauri::command]
+pub async fn adb_key_generate(team: String) -> Result<AdbKeyInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || adb_key::generate_team_key(&team))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// 导入已有的 adbkey 私钥作为团队密钥
+#[tauri::command]
+pub fn adb_key_import(team: String, path: String) -> Result<AdbKeyInfo, String> {
+    adb_key::import_team_key(&team, &path).map_err(|e| format!("{:#}", e))
+}
+
+/// 导出密钥 (adbkey + adbkey.pub) 到目录，`team` 为空时导出 adb 默认密钥
+#[tauri::command]
+pub fn adb_key_export(team: Option<String>, dir: String) -> CommandResult {
+    match adb_key::export_key(team.as_deref(), &dir) {
+        Ok(path) => CommandResult { success: true, message: format!("已导出: {}", path.display()) },
+        Err(e) => CommandResult { success: false, message: format!("导出失败: {:#}", e) },
+    }
+}
+
+/// 选择使用的密钥 (`team` 为空时使用 adb 默认密钥)
+#[tauri::command]
+pub fn adb_key_select(team: Option<String>) -> CommandResult {
+    if let Some(team) = &team {
+        match adb_key::team_key_path(team) {
+            Ok(path) if path.exists() => {}
+            Ok(_) => return CommandResult { success: false, message: format!("团队密钥不存在: {}", team) },
+            Err(e) => return CommandResult { success: false, message: e.to_string() },
+        }
+    }
+
+    let mut config = Config::load();
+    config.adb_key_team = team;
+    config.save();
+    CommandResult { success: true, message: "已切换密钥，重启 ADB 服务后生效".to_string() }
+}
+
+/// 获取 ADB Server 协议版本 (`server` 为空时查询本机 Server)
+#[tauri::command]
+pub fn adb_server_version(server: Option<String>) -> Result<u32, String> {
+    let server = match server {
+        Some(name) => adb_host::servers()
+            .into_iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| format!("未找到 ADB Server: {}", name))?,
+        None => AdbServer::local(),
+    };
+    AdbHost::new(&server.addr()).version().map_err(|e| e.to_string())
+}
+
+/// 列出 ADB Server (第一个为本机 Server)
+#[tauri::command]
+pub fn adb_servers() -> Vec<AdbServer> {
+    adb_host::servers()
+}
+
+/// 添加或修改远程 ADB Server (相当于 `adb -H <host> -P <port>`)，并开始跟踪其设备
+#[tauri::command]
+pub fn adb_server_add(app: tauri::AppHandle, name: String, host: String, port: Option<u16>) -> CommandResult {
+    let name = name.trim().to_string();
+    if name.is_empty() || name == LOCAL_SERVER {
+        return CommandResult { success: false, message: format!("无效的名称: {}", name) };
+    }
+    let server = AdbServer { name, host: host.trim().to_string(), port: port.unwrap_or(adb_host::DEFAULT_SERVER_PORT) };
+    let version = match AdbHost::new(&server.addr()).version() {
+        Ok(version) => version,
+        Err(e) => return CommandResult { success: false, message: format!("无法连接 {}: {:#}", server.addr(), e) },
+    };
+
+    let message = format!("已添加 ADB Server {} ({}，协议版本 {})", server.name, server.addr(), version);
+    let mut config = Config::load();
+    if config.adb_servers.contains(&server) {
+        return CommandResult { success: true, message };
+    }
+    config.adb_servers.retain(|s| s.name != server.name);
+    config.adb_servers.push(server.clone());
+    config.save();
+
+    // 修改已有 Server 时先停止旧的跟踪
+    crate::device_tracker::unwatch(&server.name);
+    crate::device_tracker::watch(app, server);
+    CommandResult { success: true, message }
+}
+
+/// 移除远程 ADB Server
+#[tauri::command]
+pub fn adb_server_remove(name: String) -> CommandResult {
+    let mut config = Config::load();
+    let count = config.adb_servers.len();
+    config.adb_servers.retain(|s| s.name != name);
+    if config.adb_servers.len() == count {
+        return CommandResult { success: false, message: format!("未找到 ADB Server: {}", name) };
+    }
+    config.save();
+    crate::device_tracker::unwatch(&name);
+    CommandResult { success: true, message: format!("已移除 ADB Server: {}", name) }
+}
+
 /// 无线连接
 /// 无线连接
 #[tauri::command]
@@ -43,10 +272,26 @@ pub fn pair_device(addr: String, code: String) -> CommandResult {
     scrcpy::pair_device(&addr, &code)
 }
 
+/// 开始扫码配对，返回二维码 (进度通过 qr-pair-progress 事件推送)
+#[tauri::command]
+pub fn qr_pair_start(app: tauri::AppHandle) -> Result<crate::adb_pair::QrPairing, String> {
+    crate::adb_pair::start(app).map_err(|e| format!("{:#}", e))
+}
+
+/// 取消扫码配对
+#[tauri::command]
+pub fn qr_pair_cancel() -> CommandResult {
+    if crate::adb_pair::cancel() {
+        CommandResult { success: true, message: "已取消扫码配对".to_string() }
+    } else {
+        CommandResult { success: false, message: "没有正在进行的扫码配对".to_string() }
+    }
+}
+
 /// 启用 TCP/IP 模式
 #[tauri::command]
-pub fn enable_tcpip() -> CommandResult {
-    scrcpy::enable_tcpip_mode()
+pub fn enable_tcpip(serial: Option<String>) -> CommandResult {
+    scrcpy::enable_tcpip_mode(serial.as_deref())
 }
 
 /// 断开所有连接
@@ -57,39 +302,101 @@ pub fn disconnect_all() -> CommandResult {
 
 /// 获取已安装应用
 #[tauri::command]
-pub fn get_installed_apps() -> Result<Vec<String>, String> {
-    scrcpy::get_installed_apps()
+pub fn get_installed_apps(serial: Option<String>) -> Result<Vec<String>, String> {
+    scrcpy::get_installed_apps(serial.as_deref())
 }
 
 
-/// 关闭所有 scrcpy
+/// 关闭启动器启动的所有 scrcpy
 #[tauri::command]
-pub fn kill_scrcpy() -> CommandResult {
-    scrcpy::kill_scrcpy_processes()
+pub fn kill_scrcpy(app: tauri::AppHandle) -> CommandResult {
+    scrcpy::kill_scrcpy_processes(&app)
 }
 
-/// 扫描局域网设备
+/// 列出启动器启动的 scrcpy 会话
 #[tauri::command]
-pub async fn scan_tcp_devices() -> Vec<String> {
-    scrcpy::scan_local_network().await
+pub fn session_list(sessions: tauri::State<'_, SessionManager>) -> Vec<SessionInfo> {
+    sessions.list()
+}
+
+/// 停止一个 scrcpy 会话 (退出后推送 session-ended 事件)
+#[tauri::command]
+pub fn session_stop(sessions: tauri::State<'_, SessionManager>, id: u64) -> CommandResult {
+    match sessions.stop(id) {
+        Ok(()) => CommandResult { success: true, message: "已停止".to_string() },
+        Err(e) => CommandResult { success: false, message: format!("{:#}", e) },
+    }
+}
+
+/// 检查 adb / scrcpy 的位置和版本
+#[tauri::command]
+pub fn binary_status() -> Vec<BinaryStatus> {
+    binaries::diagnose()
+}
+
+/// 指定 adb / scrcpy 路径 (为空时恢复自动查找)，返回实际使用的程序
+#[tauri::command]
+pub fn set_binary_path(tool: Tool, path: Option<String>) -> Result<ResolvedBinary, String> {
+    let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    if let Some(p) = &path {
+        if !std::path::Path::new(p).exists() {
+            return Err(format!("路径不存在: {}", p));
+        }
+    }
+
+    let mut config = Config::load();
+    match tool {
+        Tool::Adb => config.adb_path = path,
+        Tool::Scrcpy => config.scrcpy_path = path,
+    }
+    config.save();
+    binaries::resolve(tool).map_err(|e| e.to_string())
+}
+
+/// 扫描局域网设备 (发现的设备通过 scan-found 事件实时推送)
+#[tauri::command]
+pub async fn scan_tcp_devices(app: tauri::AppHandle, options: Option<ScanOptions>) -> Result<Vec<ScanResult>, String> {
+    lan_scan::scan(app, options.unwrap_or_default()).await.map_err(|e| format!("{:#}", e))
+}
+
+/// 取消局域网扫描
+#[tauri::command]
+pub fn scan_cancel() -> CommandResult {
+    if lan_scan::cancel() {
+        CommandResult { success: true, message: "已取消扫描".to_string() }
+    } else {
+        CommandResult { success: false, message: "没有正在进行的扫描".to_string() }
+    }
 }
 
 /// 启动屏幕镜像
 #[tauri::command]
-pub fn launch_mirror() -> CommandResult {
-    scrcpy::launch_scrcpy(LaunchMode::Mirror)
+pub fn launch_mirror(app: tauri::AppHandle, serial: Option<String>) -> CommandResult {
+    scrcpy::launch_scrcpy(&app, LaunchMode::Mirror, serial.as_deref())
 }
 
 /// 启动纯音频
 #[tauri::command]
-pub fn launch_audio() -> CommandResult {
-    scrcpy::launch_scrcpy(LaunchMode::Audio)
+pub fn launch_audio(app: tauri::AppHandle, serial: Option<String>) -> CommandResult {
+    scrcpy::launch_scrcpy(&app, LaunchMode::Audio, serial.as_deref())
 }
 
 /// 启动应用
 #[tauri::command]
-pub fn launch_app(package: String, settings: Option<AppSettings>, scrcpy_args: Option<String>) -> CommandResult {
-    scrcpy::launch_scrcpy(LaunchMode::App { package, settings, scrcpy_args })
+pub fn launch_app(
+    app: tauri::AppHandle,
+    package: String,
+    settings: Option<AppSettings>,
+    scrcpy_args: Option<String>,
+    serial: Option<String>,
+) -> CommandResult {
+    scrcpy::launch_scrcpy(&app, LaunchMode::App { package, settings, scrcpy_args }, serial.as_deref())
+}
+
+/// 预览启动命令 (不启动)：程序、参数及其来源、环境变量、目标设备、重复的参数和版本兼容性
+#[tauri::command]
+pub fn preview_launch(mode: LaunchMode, serial: Option<String>) -> Result<LaunchPlan, String> {
+    scrcpy::plan_launch(&mode, serial.as_deref())
 }
 
 /// 添加 ADB 历史记录
@@ -101,10 +408,14 @@ pub fn add_adb_history(ip: String) {
 
 /// 保存应用配置
 #[tauri::command]
-pub fn save_app(package: String, app_config: AppConfig) {
+pub fn save_app(package: String, app_config: AppConfig) -> Result<(), String> {
+    if let Some(args) = &app_config.scrcpy_args {
+        scrcpy_args::split(args).map_err(|e| format!("自定义参数有误: {}", e))?;
+    }
     let mut config = Config::load();
     config.apps.insert(package, app_config);
     config.save();
+    Ok(())
 }
 
 /// 删除应用
@@ -275,7 +586,9 @@ pub fn save_scrcpy_options(
     use_app_custom_args: bool,
     app_custom_args: String,
     app_options: ScrcpyOptions,
-) {
+) -> Result<(), String> {
+    scrcpy_args::split(&custom_args).map_err(|e| format!("自定义参数有误: {}", e))?;
+    scrcpy_args::split(&app_custom_args).map_err(|e| format!("应用流转自定义参数有误: {}", e))?;
     let mut config = Config::load();
     config.use_custom_args = use_custom;
     config.custom_args = custom_args;
@@ -285,6 +598,27 @@ pub fn save_scrcpy_options(
     config.app_custom_args = app_custom_args;
     config.app_stream_options = app_options;
     config.save();
+    Ok(())
+}
+
+/// 将 scrcpy 命令行 (自定义参数或 .bat 中的整行命令) 导入为结构化选项
+#[tauri::command]
+pub fn import_scrcpy_args(command_line: String) -> Result<ArgsImport, String> {
+    let args = scrcpy_args::split(&command_line).map_err(|e| format!("参数格式错误: {}", e))?;
+    Ok(scrcpy_args::from_args(&args))
+}
+
+/// 检查参数是否被当前 scrcpy 版本支持
+#[tauri::command]
+pub fn check_scrcpy_compat(command_line: String) -> Result<CompatReport, String> {
+    let args = scrcpy_args::split(&command_line).map_err(|e| format!("参数格式错误: {}", e))?;
+    Ok(scrcpy_compat::report(&args, binaries::scrcpy_version()))
+}
+
+/// 将结构化选项转换为自定义参数文本
+#[tauri::command]
+pub fn export_scrcpy_args(options: ScrcpyOptions) -> String {
+    scrcpy_args::join(&scrcpy_args::to_args(&options))
 }
 
 /// 保存托盘设置
@@ -341,52 +675,345 @@ pub fn save_first_run_config(
     config.save();
 }
 
-/// ADB 推送文件
-#[tauri::command]
-pub async fn adb_push_file(window: tauri::Window, local_path: String, remote_path: Option<String>) -> Result<CommandResult, String> {
+/// 创建向前端发送旧版进度事件 (`adb-push-progress` / `adb-pull-progress`) 的观察者
+fn progress_observer(window: &tauri::Window, event: &'static str, label: &'static str) -> ProgressObserver {
+    let window = window.clone();
+    Arc::new(move |p: &TransferProgress| {
+        let _ = window.emit(event, serde_json::json!({
+            "id": p.id,
+            "progress": p.progress,
+            "message": format!("{}: {}%", label, p.progress),
+            "rate": p.rate,
+            "eta_secs": p.eta_secs
+        }));
+    })
+}
+
+/// 解析推送目标：远程路径以 / 结尾时追加本地文件名/目录名
+fn resolve_push_target(local_path: &str, remote_path: Option<String>) -> String {
     let target = remote_path.unwrap_or_else(|| "/sdcard/Download/".to_string());
-    
-    // 处理目标路径：如果是目录（以 / 结尾），则追加文件名
-    let target_file = if target.ends_with('/') {
-        let filename = std::path::Path::new(&local_path)
+    if target.ends_with('/') {
+        let local_name = std::path::Path::new(local_path)
             .file_name()
             .and_then(|f| f.to_str())
             .unwrap_or("unknown_file");
-        // ADB SYNC 协议通常需要 "路径,权限" 格式，或者至少完整路径
-        // 我们尝试发送 "路径,0644" 以确保权限正确 (标准 adb push 行为)
-        format!("{}{},0644", target, filename)
+        format!("{}{}", target, local_name)
     } else {
-        format!("{},0644", target)
+        target
+    }
+}
+
+/// 解析拉取目标：未指定时保存到系统下载目录，指定目录时追加文件名
+fn resolve_pull_target(remote_path: &str, local_path: Option<String>) -> Result<String, String> {
+    let filename = remote_path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|f| !f.is_empty())
+        .unwrap_or("unknown_file")
+        .to_string();
+
+    let target = match local_path {
+        Some(p) if !std::path::Path::new(&p).is_dir() => std::path::PathBuf::from(p),
+        Some(p) => std::path::Path::new(&p).join(&filename),
+        None => dirs::download_dir()
+            .ok_or_else(|| "无法确定下载目录".to_string())?
+            .join(&filename),
     };
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// 解析八进制权限字符串 (如 "755")
+fn parse_mode(mode: Option<String>) -> Result<Option<u32>, String> {
+    match mode.as_deref().map(str::trim).filter(|m| !m.is_empty()) {
+        Some(m) => u32::from_str_radix(m.trim_start_matches("0o"), 8)
+            .map(Some)
+            .map_err(|_| format!("无效的权限: {}", m)),
+        None => Ok(None),
+    }
+}
+
+/// 把传输的最终状态转换为命令结果
+fn transfer_result(info: &TransferInfo, done_message: String) -> CommandResult {
+    match info.status {
+        TransferStatus::Completed => CommandResult {
+            success: true,
+            message: match &info.stats {
+                Some(stats) => format!("{} ({})", done_message, stats),
+                None => done_message,
+            },
+        },
+        TransferStatus::Cancelled => CommandResult {
+            success: false,
+            message: "传输已取消".to_string(),
+        },
+        _ => {
+            let mut message = format!("传输失败: {}", info.error.clone().unwrap_or_default());
+            for failure in &info.failures {
+                message.push_str(&format!("\n失败: {} ({})", failure.path, failure.error));
+            }
+            CommandResult { success: false, message }
+        }
+    }
+}
+
+/// ADB 推送文件 (支持目录递归推送)
+///
+/// 通过传输管理器执行并等待结束，传输 ID 可从 `transfer-status` 事件获取并用于取消。
+#[tauri::command]
+pub async fn adb_push_file(
+    window: tauri::Window,
+    transfers: tauri::State<'_, TransferManager>,
+    local_path: String,
+    remote_path: Option<String>,
+    mode: Option<String>,
+    serial: Option<String>,
+) -> Result<CommandResult, String> {
+    let request = TransferRequest {
+        kind: TransferKind::Push,
+        serial: Some(scrcpy::resolve_device_async(serial).await?),
+        remote_path: resolve_push_target(&local_path, remote_path),
+        local_path: local_path.clone(),
+        mode: parse_mode(mode)?,
+    };
+    let observer = progress_observer(&window, "adb-push-progress", "传输中");
+    let (_, handle) = transfers.submit(window.app_handle().clone(), request, Some(observer));
+    let info = handle.await.map_err(|e| e.to_string())?;
+
+    if info.status == TransferStatus::Completed {
+        // 发送 100% 进度
+        let _ = window.emit("adb-push-progress", serde_json::json!({
+            "id": info.id,
+            "progress": 100,
+            "message": "传输完成",
+            "stats": info.stats
+        }));
+    }
+
+    Ok(transfer_result(&info, format!("发送成功: {}", local_path)))
+}
+
+/// ADB 拉取文件
+#[tauri::command]
+pub async fn adb_pull_file(
+    window: tauri::Window,
+    transfers: tauri::State<'_, TransferManager>,
+    remote_path: String,
+    local_path: Option<String>,
+    serial: Option<String>,
+) -> Result<CommandResult, String> {
+    let target = resolve_pull_target(&remote_path, local_path)?;
+    let request = TransferRequest {
+        kind: TransferKind::Pull,
+        serial: Some(scrcpy::resolve_device_async(serial).await?),
+        local_path: target.clone(),
+        remote_path,
+        mode: None,
+    };
+    let observer = progress_observer(&window, "adb-pull-progress", "接收中");
+    let (_, handle) = transfers.submit(window.app_handle().clone(), request, Some(observer));
+    let info = handle.await.map_err(|e| e.to_string())?;
+
+    if info.status == TransferStatus::Completed {
+        // 发送 100% 进度
+        let _ = window.emit("adb-pull-progress", serde_json::json!({
+            "id": info.id,
+            "progress": 100,
+            "message": "接收完成",
+            "stats": info.stats
+        }));
+    }
+
+    Ok(transfer_result(&info, format!("已保存到: {}", target)))
+}
+
+/// 提交推送任务 (立即返回传输 ID，进度见 `transfer-progress` 事件)
+#[tauri::command]
+pub async fn transfer_push(
+    app: tauri::AppHandle,
+    transfers: tauri::State<'_, TransferManager>,
+    local_path: String,
+    remote_path: Option<String>,
+    mode: Option<String>,
+    serial: Option<String>,
+) -> Result<u64, String> {
+    let request = TransferRequest {
+        kind: TransferKind::Push,
+        serial: Some(scrcpy::resolve_device_async(serial).await?),
+        remote_path: resolve_push_target(&local_path, remote_path),
+        local_path,
+        mode: parse_mode(mode)?,
+    };
+    Ok(transfers.submit(app, request, None).0)
+}
+
+/// 提交拉取任务 (立即返回传输 ID)
+#[tauri::command]
+pub async fn transfer_pull(
+    app: tauri::AppHandle,
+    transfers: tauri::State<'_, TransferManager>,
+    remote_path: String,
+    local_path: Option<String>,
+    serial: Option<String>,
+) -> Result<u64, String> {
+    let request = TransferRequest {
+        kind: TransferKind::Pull,
+        serial: Some(scrcpy::resolve_device_async(serial).await?),
+        local_path: resolve_pull_target(&remote_path, local_path)?,
+        remote_path,
+        mode: None,
+    };
+    Ok(transfers.submit(app, request, None).0)
+}
+
+/// 取消传输
+#[tauri::command]
+pub fn transfer_cancel(transfers: tauri::State<'_, TransferManager>, id: u64) -> bool {
+    transfers.cancel(id)
+}
+
+/// 获取排队中和进行中的传输
+#[tauri::command]
+pub fn transfer_list(transfers: tauri::State<'_, TransferManager>) -> Vec<TransferInfo> {
+    transfers.list()
+}
+
+/// 获取已结束的传输记录
+#[tauri::command]
+pub fn transfer_history(transfers: tauri::State<'_, TransferManager>) -> Vec<TransferInfo> {
+    transfers.history()
+}
+
+/// 清空传输记录
+#[tauri::command]
+pub fn transfer_clear_history(transfers: tauri::State<'_, TransferManager>) {
+    transfers.clear_history();
+}
+
+/// 保存传输设置 (并发数、重试次数)
+#[tauri::command]
+pub fn save_transfer_settings(transfers: tauri::State<'_, TransferManager>, concurrency: u32, max_retries: u32) {
+    let mut config = Config::load();
+    config.transfer_concurrency = concurrency.max(1);
+    config.transfer_max_retries = max_retries;
+    config.save();
+
+    transfers.set_concurrency(config.transfer_concurrency);
+    transfers.set_max_retries(max_retries);
+}
+
+/// 安装 APK 或分包压缩包 .apks/.xapk/.apkm (进度通过 `adb-install-progress` 事件发送)
+#[tauri::command]
+pub async fn adb_install_apk(
+    window: tauri::Window,
+    apk_path: String,
+    options: Option<InstallOptions>,
+    serial: Option<String>,
+) -> Result<InstallReport, String> {
+    let serial = scrcpy::resolve_device_async(serial).await?;
+    let installer = AdbInstaller::new(Some(serial));
 
-    // 使用原生 TCP 连接进行传输
-    let pusher = AdbPusher::new(None); 
-    
     let window_clone = window.clone();
-    
-    pusher.push(&local_path, &target_file, Some(Box::new(move |current, total| {
-        let percent = if total > 0 {
-            (current as f64 / total as f64) * 100.0
-        } else { 
-            0.0 
-        };
-        
-        let _ = window_clone.emit("adb-push-progress", serde_json::json!({
-            "progress": percent as u32,
-            "message": format!("传输中: {:.1}%", percent)
+    let callback: ProgressCallback = Box::new(move |sent, total| {
+        let percent = (sent * 100).checked_div(total).unwrap_or(0);
+        let message = if sent >= total { "正在安装...".to_string() } else { format!("发送中: {}%", percent) };
+        let _ = window_clone.emit("adb-install-progress", serde_json::json!({
+            "progress": percent,
+            "message": message
         }));
-    }))).await.map_err(|e| e.to_string())?;
-    
-    // 发送 100% 进度
-    let _ = window.emit("adb-push-progress", serde_json::json!({
-        "progress": 100,
-        "message": "传输完成"
-    }));
-
-    Ok(CommandResult {
-        success: true,
-        message: format!("发送成功: {}", local_path),
-    })
+    });
+
+    let name = std::path::Path::new(&apk_path)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| apk_path.clone());
+
+    let options = options.unwrap_or_default();
+    let result = if apk_bundle::is_bundle(&apk_path) {
+        installer.install_bundle(&apk_path, &options, Some(callback)).await
+            .map(|splits| (InstallMethod::Session, splits))
+    } else {
+        installer.install(&apk_path, &options, Some(callback)).await
+            .map(|method| (method, Vec::new()))
+    };
+
+    let report = match result {
+        Ok((method, splits)) => {
+            // 发送 100% 进度
+            let _ = window.emit("adb-install-progress", serde_json::json!({
+                "progress": 100,
+                "message": "安装完成"
+            }));
+            let message = if splits.is_empty() {
+                format!("安装成功: {}", name)
+            } else {
+                let names: Vec<&str> = splits.iter().map(|s| s.name.as_str()).collect();
+                format!("安装成功: {} ({})", name, names.join(", "))
+            };
+            InstallReport { success: true, message, method: Some(method), error: None, splits }
+        }
+        Err(e) => {
+            let error = e.downcast_ref::<InstallError>().cloned();
+            InstallReport { success: false, message: format!("安装失败: {:#}", e), method: None, error, splits: Vec::new() }
+        }
+    };
+    Ok(report)
+}
+
+/// 为指定设备 (默认当前设备) 创建 AdbPusher
+async fn device_pusher(serial: Option<String>) -> Result<AdbPusher, String> {
+    Ok(AdbPusher::new(Some(scrcpy::resolve_device_async(serial).await?)))
+}
+
+/// 查询设备上的文件信息
+#[tauri::command]
+pub async fn adb_stat(path: String, serial: Option<String>) -> Result<Option<RemoteEntry>, String> {
+    device_pusher(serial).await?.stat(&path).await.map_err(|e| e.to_string())
+}
+
+/// 列出设备上的目录内容
+#[tauri::command]
+pub async fn adb_list_dir(path: String, serial: Option<String>) -> Result<Vec<RemoteEntry>, String> {
+    device_pusher(serial).await?.list(&path).await.map_err(|e| e.to_string())
+}
+
+/// 删除设备上的文件或目录
+#[tauri::command]
+pub async fn adb_delete(path: String, serial: Option<String>) -> CommandResult {
+    let pusher = match device_pusher(serial).await {
+        Ok(pusher) => pusher,
+        Err(message) => return CommandResult { success: false, message },
+    };
+    match pusher.delete(&path).await {
+        Ok(()) => CommandResult { success: true, message: format!("已删除: {}", path) },
+        Err(e) => CommandResult { success: false, message: format!("删除失败: {}", e) },
+    }
+}
+
+/// 在设备上创建目录
+#[tauri::command]
+pub async fn adb_mkdir(path: String, serial: Option<String>) -> CommandResult {
+    let pusher = match device_pusher(serial).await {
+        Ok(pusher) => pusher,
+        Err(message) => return CommandResult { success: false, message },
+    };
+    match pusher.mkdir(&path).await {
+        Ok(()) => CommandResult { success: true, message: format!("已创建: {}", path) },
+        Err(e) => CommandResult { success: false, message: format!("创建失败: {}", e) },
+    }
+}
+
+/// 重命名/移动设备上的文件
+#[tauri::command]
+pub async fn adb_rename(from: String, to: String, serial: Option<String>) -> CommandResult {
+    let pusher = match device_pusher(serial).await {
+        Ok(pusher) => pusher,
+        Err(message) => return CommandResult { success: false, message },
+    };
+    match pusher.rename(&from, &to).await {
+        Ok(()) => CommandResult { success: true, message: format!("已重命名为: {}", to) },
+        Err(e) => CommandResult { success: false, message: format!("重命名失败: {}", e) },
+    }
 }
 
 /// 应用宝搜索结果结构
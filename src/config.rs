@@ -11,6 +11,10 @@ use std::collections::HashMap;
 use indexmap::IndexMap;
 use std::fs;
 use std::path::PathBuf;
+use crate::adb_forward::ForwardRule;
+use crate::adb_host::AdbServer;
+use crate::adb_transport::AdbBackend;
+use crate::scrcpy_args::{AudioCodec, AudioSource, Crop, InputMode, Orientation, ShortcutKey, VideoCodec};
 
 /// 配置文件名
 const CONFIG_FILE: &str = "config.json";
@@ -79,8 +83,11 @@ pub struct PresetConfig {
 }
 
 /// Scrcpy 启动选项
+///
+/// 数值为 0、可选项为空时使用 scrcpy 默认值，生成的参数见 `scrcpy_args::to_args`。
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ScrcpyOptions {
+    /// 使用 HID 键盘 (-K)，设置了 `keyboard` 时忽略
     pub hid_keyboard: bool,
     pub stay_awake: bool,
     pub turn_screen_off: bool,
@@ -91,6 +98,64 @@ pub struct ScrcpyOptions {
     pub local_ime: bool,
     pub max_size: u32,
     pub max_fps: u32,
+    /// 视频编码
+    #[serde(default)]
+    pub video_codec: Option<VideoCodec>,
+    /// 视频编码器名称 (如 OMX.qcom.video.encoder.avc)
+    #[serde(default)]
+    pub video_encoder: Option<String>,
+    /// 视频码率 (bps)
+    #[serde(default)]
+    pub video_bit_rate: u32,
+    /// 音频编码
+    #[serde(default)]
+    pub audio_codec: Option<AudioCodec>,
+    /// 音频来源
+    #[serde(default)]
+    pub audio_source: Option<AudioSource>,
+    /// 音频码率 (bps)
+    #[serde(default)]
+    pub audio_bit_rate: u32,
+    /// 音频缓冲 (毫秒)
+    #[serde(default)]
+    pub audio_buffer: u32,
+    /// 裁剪区域
+    #[serde(default)]
+    pub crop: Option<Crop>,
+    /// 画面方向
+    #[serde(default)]
+    pub orientation: Option<Orientation>,
+    /// 镜像的显示器 ID
+    #[serde(default)]
+    pub display_id: Option<u32>,
+    /// 键盘输入方式
+    #[serde(default)]
+    pub keyboard: Option<InputMode>,
+    /// 鼠标输入方式
+    #[serde(default)]
+    pub mouse: Option<InputMode>,
+    /// 快捷键修饰键，为空时使用 scrcpy 默认值 (lalt,lsuper)
+    #[serde(default)]
+    pub shortcut_mod: Vec<ShortcutKey>,
+    /// 窗口标题
+    #[serde(default)]
+    pub window_title: Option<String>,
+    /// 窗口位置
+    #[serde(default)]
+    pub window_x: Option<i32>,
+    #[serde(default)]
+    pub window_y: Option<i32>,
+    /// 窗口大小
+    #[serde(default)]
+    pub window_width: u32,
+    #[serde(default)]
+    pub window_height: u32,
+    /// 无操作多少秒后关闭设备屏幕
+    #[serde(default)]
+    pub screen_off_timeout: u32,
+    /// 其它参数 (从命令行导入时无法映射的参数)，原样追加在最后
+    #[serde(default)]
+    pub extra_args: Vec<String>,
 }
 
 impl Default for ScrcpyOptions {
@@ -106,6 +171,26 @@ impl Default for ScrcpyOptions {
             local_ime: false,
             max_size: 0,
             max_fps: 0,
+            video_codec: None,
+            video_encoder: None,
+            video_bit_rate: 0,
+            audio_codec: None,
+            audio_source: None,
+            audio_bit_rate: 0,
+            audio_buffer: 0,
+            crop: None,
+            orientation: None,
+            display_id: None,
+            keyboard: None,
+            mouse: None,
+            shortcut_mod: Vec::new(),
+            window_title: None,
+            window_x: None,
+            window_y: None,
+            window_width: 0,
+            window_height: 0,
+            screen_off_timeout: 0,
+            extra_args: Vec::new(),
         }
     }
 }
@@ -128,6 +213,41 @@ pub struct Config {
     pub tray_app_count: u32,
     pub tray_show_mirror: bool,
     pub tray_show_audio: bool,
+    /// 同时进行的文件传输数
+    #[serde(default = "default_transfer_concurrency")]
+    pub transfer_concurrency: u32,
+    /// 传输失败后的最大重试次数
+    #[serde(default = "default_transfer_max_retries")]
+    pub transfer_max_retries: u32,
+    /// 当前选择的设备序列号 (连接多台设备时使用)
+    #[serde(default)]
+    pub active_device: Option<String>,
+    /// 各设备的端口转发规则 (序列号 -> 规则)，设备连接时自动应用
+    #[serde(default)]
+    pub forward_rules: HashMap<String, Vec<ForwardRule>>,
+    /// 各设备的连接方式 (序列号 -> 方式)，未设置的设备通过 ADB Server 连接
+    #[serde(default)]
+    pub device_backends: HashMap<String, AdbBackend>,
+    /// 使用的团队密钥 (keys/<团队>/adbkey)，None 时使用 adb 默认密钥
+    #[serde(default)]
+    pub adb_key_team: Option<String>,
+    /// 远程 ADB Server (本机 Server 不在列表中)
+    #[serde(default)]
+    pub adb_servers: Vec<AdbServer>,
+    /// 指定的 adb 路径 (文件或所在目录)，None 时自动查找
+    #[serde(default)]
+    pub adb_path: Option<String>,
+    /// 指定的 scrcpy 路径 (文件或所在目录)，None 时自动查找
+    #[serde(default)]
+    pub scrcpy_path: Option<String>,
+}
+
+fn default_transfer_concurrency() -> u32 {
+    2
+}
+
+fn default_transfer_max_retries() -> u32 {
+    2
 }
 
 impl Default for Config {
@@ -171,6 +291,15 @@ impl Default for Config {
             tray_app_count: 4,
             tray_show_mirror: true,
             tray_show_audio: true,
+            transfer_concurrency: default_transfer_concurrency(),
+            transfer_max_retries: default_transfer_max_retries(),
+            active_device: None,
+            forward_rules: HashMap::new(),
+            device_backends: HashMap::new(),
+            adb_key_team: None,
+            adb_servers: Vec::new(),
+            adb_path: None,
+            scrcpy_path: None,
         }
     }
 }
@@ -0,0 +1,334 @@
+//! Scrcpy Launcher - 设备实时跟踪模块
+//!
+//! 后台线程保持一条 `host:track-devices-l` 连接，ADB Server 在设备变化时推送完整列表，
+//! 与上一次的列表比较后发出事件：
+//! - `device-added` - 新设备出现 (载荷为 AdbDevice)
+//! - `device-removed` - 设备断开 (载荷为最后一次的 AdbDevice)
+//! - `device-state-changed` - 状态变化，如 unauthorized -> device
+//!
+//! 本机和每个远程 ADB Server 各有一个跟踪线程，连接断开 (如 ADB Server 重启) 后自动重连，
+//! 托盘提示和菜单显示全部 Server 的设备。
+//! 设备变为可用时重新应用 `Config.forward_rules` 中保存的端口转发。
+
+use std::collections::HashMap;
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+use crate::adb_forward::AdbForwarder;
+use crate::adb_host::{self, AdbDevice, AdbHost, AdbServer, DeviceState};
+use crate::config::Config;
+
+/// 重连间隔
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 设备列表的一次变化
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeviceChange {
+    Added { device: AdbDevice },
+    Removed { device: AdbDevice },
+    StateChanged { device: AdbDevice, previous: DeviceState },
+}
+
+impl DeviceChange {
+    /// 对应的前端事件名
+    fn event(&self) -> &'static str {
+        match self {
+            DeviceChange::Added { .. } => "device-added",
+            DeviceChange::Removed { .. } => "device-removed",
+            DeviceChange::StateChanged { .. } => "device-state-changed",
+        }
+    }
+}
+
+/// 比较前后两次设备列表 (按序列号匹配)
+pub fn diff_devices(old: &[AdbDevice], new: &[AdbDevice]) -> Vec<DeviceChange> {
+    let mut changes = Vec::new();
+
+    for device in old {
+        if !new.iter().any(|d| d.serial == device.serial) {
+            changes.push(DeviceChange::Removed { device: device.clone() });
+        }
+    }
+
+    for device in new {
+        match old.iter().find(|d| d.serial == device.serial) {
+            None => changes.push(DeviceChange::Added { device: device.clone() }),
+            Some(prev) if prev.state != device.state => changes.push(DeviceChange::StateChanged {
+                device: device.clone(),
+                previous: prev.state.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    changes
+}
+
+/// 一个 Server 最近一次的设备列表及报告它的跟踪线程
+struct ServerDevices {
+    generation: u64,
+    devices: Vec<AdbDevice>,
+}
+
+/// 各 Server 最近一次的设备列表 (Server 名称 -> 设备)
+static SERVER_DEVICES: OnceLock<Mutex<HashMap<String, ServerDevices>>> = OnceLock::new();
+
+/// 跟踪线程的序号，修改 Server 后新线程的序号更大
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 正在跟踪的连接 (Server 名称 -> 连接)，用于停止跟踪
+static TRACKING: OnceLock<Mutex<HashMap<String, (u64, TcpStream)>>> = OnceLock::new();
+
+fn tracking() -> &'static Mutex<HashMap<String, (u64, TcpStream)>> {
+    TRACKING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 跟踪线程最近报告的远程 Server 设备 (按 Server 顺序，不含本机)
+pub fn remote_devices() -> Vec<AdbDevice> {
+    let server_devices = SERVER_DEVICES.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    adb_host::servers()
+        .iter()
+        .filter(|s| !s.is_local())
+        .filter_map(|s| server_devices.get(&s.name))
+        .flat_map(|s| s.devices.iter())
+        .cloned()
+        .collect()
+}
+
+/// 为本机和所有远程 ADB Server 启动跟踪线程
+///
+/// 不会主动启动 ADB Server：Server 未运行时每隔几秒重试，
+/// 由状态检查等操作启动 Server 后自动连上。
+pub fn start<R: Runtime>(app: AppHandle<R>) {
+    for server in adb_host::servers() {
+        watch(app.clone(), server);
+    }
+}
+
+/// 启动一个 ADB Server 的跟踪线程，Server 从配置中移除或修改后线程退出
+///
+/// 修改 Server 时同名的新线程会接管它的设备列表，旧线程退出时不会清掉新线程报告的设备。
+pub fn watch<R: Runtime>(app: AppHandle<R>, server: AdbServer) {
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    std::thread::spawn(move || {
+        let host = AdbHost::new(&server.addr());
+        let mut known: Vec<AdbDevice> = Vec::new();
+        // 上一次的错误，Server 一直不可用时只输出一次
+        let mut last_error: Option<String> = None;
+
+        while adb_host::servers().contains(&server) {
+            if let Err(e) = track(&host, &server, generation, &app, &mut known, &mut last_error) {
+                let message = e.to_string();
+                if last_error.as_ref() != Some(&message) {
+                    eprintln!("Device tracking on {} interrupted: {}", server.name, message);
+                }
+                last_error = Some(message);
+            }
+            std::thread::sleep(RECONNECT_INTERVAL);
+        }
+
+        // Server 已移除或修改：它的设备全部视为断开
+        update(&app, &server, generation, Vec::new(), &mut known);
+    });
+}
+
+/// 停止跟踪 Server (在从配置中移除或修改后调用)
+///
+/// 此时仍在建立连接的旧线程登记连接后会重新检查配置并自行退出。
+pub fn unwatch(name: &str) {
+    if let Some((_, stream)) = tracking().lock().unwrap().remove(name) {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// 登记跟踪连接供 `unwatch` 停止，同名的新线程已登记时返回 false
+fn register_tracking(server: &AdbServer, generation: u64, stream: TcpStream) -> bool {
+    let mut tracking = tracking().lock().unwrap();
+    if tracking.get(&server.name).is_some_and(|(g, _)| *g > generation) {
+        return false;
+    }
+    tracking.insert(server.name.clone(), (generation, stream));
+    true
+}
+
+/// 移除自己登记的跟踪连接 (不影响同名新线程的连接)
+fn unregister_tracking(server: &AdbServer, generation: u64) {
+    let mut tracking = tracking().lock().unwrap();
+    if tracking.get(&server.name).is_some_and(|(g, _)| *g == generation) {
+        tracking.remove(&server.name);
+    }
+}
+
+/// 保持一条跟踪连接直到断开，或 Server 已从配置中移除或修改
+///
+/// 断开时保留已知设备列表，重连后收到的第一份列表会与之比较，
+/// 因此 Server 重启期间拔掉的设备也会产生 `device-removed`。
+fn track<R: Runtime>(
+    host: &AdbHost,
+    server: &AdbServer,
+    generation: u64,
+    app: &AppHandle<R>,
+    known: &mut Vec<AdbDevice>,
+    last_error: &mut Option<String>,
+) -> anyhow::Result<()> {
+    let mut stream = host.request("host:track-devices-l")?;
+    *last_error = None;
+    // 跟踪连接可能长时间没有数据
+    stream.set_read_timeout(None)?;
+    if !register_tracking(server, generation, stream.try_clone()?) {
+        return Ok(());
+    }
+
+    let result = read_updates(&mut stream, server, generation, app, known);
+    unregister_tracking(server, generation);
+    result
+}
+
+/// 读取设备列表并更新，每次都确认 Server 仍在配置中
+/// (登记连接前 Server 可能已被修改，`unwatch` 停不到这条连接)
+fn read_updates<R: Runtime>(
+    stream: &mut TcpStream,
+    server: &AdbServer,
+    generation: u64,
+    app: &AppHandle<R>,
+    known: &mut Vec<AdbDevice>,
+) -> anyhow::Result<()> {
+    while adb_host::servers().contains(server) {
+        let mut devices = adb_host::parse_devices(&adb_host::read_length_prefixed(stream)?);
+        if !adb_host::servers().contains(server) {
+            break;
+        }
+        for device in &mut devices {
+            device.server = server.name.clone();
+        }
+        update(app, server, generation, devices, known);
+    }
+    Ok(())
+}
+
+/// 已被同名新线程接管时，只保留新线程列表中没有的设备的断开事件
+fn superseded_changes(changes: Vec<DeviceChange>, current: &[AdbDevice]) -> Vec<DeviceChange> {
+    changes
+        .into_iter()
+        .filter(|change| match change {
+            DeviceChange::Removed { device } => !current.iter().any(|d| d.serial == device.serial),
+            _ => false,
+        })
+        .collect()
+}
+
+/// 与已知列表比较，发出事件并更新托盘
+///
+/// `generation` 小于已记录的序号时说明同名 Server 已由新线程接管，不再修改设备列表。
+fn update<R: Runtime>(
+    app: &AppHandle<R>,
+    server: &AdbServer,
+    generation: u64,
+    devices: Vec<AdbDevice>,
+    known: &mut Vec<AdbDevice>,
+) {
+    let changes = diff_devices(known, &devices);
+    if changes.is_empty() {
+        return;
+    }
+
+    let (changes, all) = {
+        let mut server_devices = SERVER_DEVICES.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+        match server_devices.get(&server.name) {
+            Some(current) if current.generation > generation => {
+                (superseded_changes(changes, &current.devices), None)
+            }
+            _ => {
+                adb_host::record_devices(&server.name, &devices);
+                server_devices.insert(server.name.clone(), ServerDevices { generation, devices: devices.clone() });
+                // 托盘显示全部 Server 的设备 (按 Server 顺序)
+                let all: Vec<AdbDevice> = adb_host::servers()
+                    .iter()
+                    .filter_map(|s| server_devices.get(&s.name))
+                    .flat_map(|s| s.devices.iter())
+                    .cloned()
+                    .collect();
+                (changes, Some(all))
+            }
+        }
+    };
+
+    for change in &changes {
+        let _ = app.emit(change.event(), change);
+    }
+    *known = devices;
+    let Some(all) = all else {
+        return;
+    };
+    crate::tray::update_devices(app, &all);
+
+    for change in &changes {
+        if let DeviceChange::Added { device } | DeviceChange::StateChanged { device, .. } = change {
+            if device.is_ready() {
+                apply_forward_rules(&device.serial);
+            }
+        }
+    }
+}
+
+/// 在后台重新应用设备保存的转发规则
+fn apply_forward_rules(serial: &str) {
+    let Some(rules) = Config::load().forward_rules.remove(serial) else {
+        return;
+    };
+    let serial = serial.to_string();
+    std::thread::spawn(move || {
+        for (rule, error) in AdbForwarder::new(serial.clone()).apply(&rules) {
+            eprintln!("Failed to apply forward {} -> {} on {}: {}", rule.local, rule.remote, serial, error);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_devices() {
+        let old = adb_host::parse_devices("\
+emulator-5554 device product:sdk model:Pixel_7 transport_id:1
+192.168.1.5:5555 unauthorized transport_id:2
+");
+        let new = adb_host::parse_devices("\
+192.168.1.5:5555 device product:redfin model:Pixel_5 transport_id:2
+R58M123 device product:a52 model:SM_A525F transport_id:3
+");
+        let changes = diff_devices(&old, &new);
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(&changes[0], DeviceChange::Removed { device } if device.serial == "emulator-5554"));
+        assert!(matches!(
+            &changes[1],
+            DeviceChange::StateChanged { device, previous: DeviceState::Unauthorized } if device.is_ready()
+        ));
+        assert!(matches!(&changes[2], DeviceChange::Added { device } if device.serial == "R58M123"));
+
+        assert!(diff_devices(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_superseded_changes() {
+        // 旧线程退出时的设备列表与新线程已报告的列表
+        let old = adb_host::parse_devices("\
+192.168.1.5:5555 device product:redfin model:Pixel_5 transport_id:2
+R58M123 device product:a52 model:SM_A525F transport_id:3
+");
+        let current = adb_host::parse_devices("\
+R58M123 device product:a52 model:SM_A525F transport_id:7
+");
+        let changes = superseded_changes(diff_devices(&old, &[]), &current);
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], DeviceChange::Removed { device } if device.serial == "192.168.1.5:5555"));
+
+        let added = diff_devices(&[], &current);
+        assert!(superseded_changes(added, &[]).is_empty());
+    }
+}
@@ -0,0 +1,481 @@
+//! Scrcpy Launcher - 局域网扫描模块
+//!
+//! 两种发现方式并行：
+//! - 端口扫描：对指定网段 (默认本机所在的 /24 网段) 的指定端口 (默认 5555) 发起连接，
+//!   端口打开后发送 CNXN 确认是 adbd，打印机等其他服务会被排除
+//! - mDNS：Android 11+ 无线调试广播的连接/配对服务 (随机端口，见 `adb_mdns`)
+//!
+//! 扫描以会话形式运行：并发连接数有上限，每发现一台设备立即推送 `scan-found` 事件，
+//! 结束时推送 `scan-finished`；同一时间只有一个会话，可随时取消。
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{Result, bail, Context};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::adb_key::AdbKey;
+use crate::adb_mdns::{self, MdnsService, MdnsServiceKind};
+use crate::adb_transport::{self, ProbeResult};
+
+/// mDNS 浏览时长
+const MDNS_BROWSE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 端口打开后等待 ADB 握手应答的时间
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// 单次扫描最多的目标地址数 (相当于一个 /16 网段)
+const MAX_TARGETS: usize = 65536;
+
+/// 当前扫描会话的取消标记
+static CURRENT: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// 扫描参数 (均可省略)
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// 网段，如 `192.168.1.0/24`，也可以是单个 IP；为空时扫描本机所在的 /24 网段
+    pub cidrs: Vec<String>,
+    /// 端口列表
+    pub ports: Vec<u16>,
+    /// 连接超时 (毫秒)
+    pub timeout_ms: u64,
+    /// 最大并发连接数
+    pub concurrency: usize,
+    /// 是否同时进行 mDNS 发现
+    pub mdns: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            cidrs: Vec::new(),
+            ports: vec![5555],
+            timeout_ms: 150,
+            concurrency: 128,
+            mdns: true,
+        }
+    }
+}
+
+/// 局域网扫描发现的设备
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ScanResult {
+    pub ip: String,
+    pub port: u16,
+    /// mDNS 服务名 (端口扫描发现的设备为空)
+    pub service: Option<String>,
+    /// mDNS 主机名
+    pub host: Option<String>,
+    /// 是否为配对服务 (需要先用配对码配对)
+    pub pairing: bool,
+    /// 设备型号 (ADB 握手得到，需要授权时为空)
+    pub model: Option<String>,
+    /// 产品名
+    pub product: Option<String>,
+    /// 设备要求授权 (不信任当前密钥，连接时需要在手机上确认)
+    pub auth_required: bool,
+}
+
+impl ScanResult {
+    /// 根据握手结果构造端口扫描的条目
+    fn from_probe(ip: String, port: u16, probe: ProbeResult) -> Self {
+        let (banner, auth_required) = match probe {
+            ProbeResult::Device(banner) => (banner, false),
+            ProbeResult::AuthRequired | ProbeResult::TlsRequired => (Default::default(), true),
+        };
+        Self {
+            ip,
+            port,
+            service: None,
+            host: None,
+            pairing: false,
+            model: banner.model,
+            product: banner.product,
+            auth_required,
+        }
+    }
+
+    fn from_mdns(service: &MdnsService) -> Self {
+        Self {
+            ip: service.ip.clone(),
+            port: service.port,
+            service: Some(service.name.clone()),
+            host: Some(service.host.clone()),
+            pairing: service.kind == MdnsServiceKind::Pairing,
+            model: None,
+            product: None,
+            auth_required: false,
+        }
+    }
+}
+
+/// 扫描结束事件载荷
+#[derive(Serialize, Clone, Debug)]
+struct ScanFinished {
+    count: usize,
+    cancelled: bool,
+}
+
+/// 通过 ipconfig 获取所有本机 IPv4 地址，并过滤掉虚拟网卡
+fn get_all_local_ips() -> Vec<String> {
+    let output = std::process::Command::new("ipconfig")
+        .output()
+        .ok();
+
+    let mut ips = Vec::new();
+
+    if let Some(output) = output {
+        // Windows 的 ipconfig 输出编码通常是 GBK (中文环境)，但 `String::from_utf8_lossy` 处理 GBK 会乱码。
+        // 不过我们主要匹配 "IPv4" (ASCII) 和 ":", 以及 IP 数字。
+        // 适配器名称如果是中文可能会乱码，导致过滤失效。
+        // 这是一个潜在风险点。但通常 Virtual/VMware 等关键词是英文。
+        // "vEthernet" 也是英文。
+        // 如果能检测到 "Virtual", "Pseudo", "VMware", "Box", "VPN" 等关键词最好。
+        
+        // 稍微优化：尝试 decode GBK 最好，但引入依赖麻烦。
+        // 这里假设关键的虚拟网卡标识通常包含英文部分，或者通过 IP 特征辅助过滤。
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut current_adapter = String::new();
+
+        for line in stdout.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // 适配器行通常以 ":" 结尾，且不包含 " . . ."
+            // 例如 "Ethernet adapter Ethernet:" 或 "以太网适配器 vEthernet (WSL):"
+            if line.ends_with(':') && !line.contains(". . .") {
+                current_adapter = line.to_lowercase();
+                continue;
+            }
+
+            // 匹配 IPv4
+            if line.contains("IPv4") && line.contains(":") {
+                // 检查当前适配器是否应该被忽略
+                if current_adapter.contains("vmware") 
+                    || current_adapter.contains("virtual") 
+                    || current_adapter.contains("vethernet") // Hyper-V / WSL
+                    || current_adapter.contains("pseudo")
+                    || current_adapter.contains("tap-windows")
+                    || current_adapter.contains("vpn")
+                    || current_adapter.contains("tun")
+                    || current_adapter.contains("singbox")
+                    || current_adapter.contains("wsl")
+                {
+                    continue;
+                }
+
+                let parts: Vec<&str> = line.split(':').collect();
+                if let Some(ip_part) = parts.last() {
+                    let ip = ip_part.trim().to_string();
+                    
+                    // 过滤 IP 特征
+                    if ip == "127.0.0.1" { continue; }
+                    
+                    // 过滤常见的 TUN/Fake IP 网段
+                    // 198.18.0.0/15 是保留用于性能测试的，常被 TUN 模式用来做 Fake IP
+                    if ip.starts_with("198.18.") { continue; }
+                    
+                    // 169.254.x.x (APIPA)
+                    if ip.starts_with("169.254.") { continue; }
+
+                    if ip.split('.').count() == 4 {
+                        ips.push(ip);
+                    }
+                }
+            }
+        }
+    }
+    
+    // 兜底：如果过滤太严格导致没 IP 了，尝试 UDP 方式（至少能拿到一个出网 IP）
+    if ips.is_empty() {
+         if let Some(ip) = get_local_ip_udp() {
+             // 再次检查 UDP 拿到的 IP 是否也是 TUN IP
+             if !ip.starts_with("198.18.") {
+                 ips.push(ip);
+             }
+         }
+    }
+
+    ips
+}
+
+/// UDP 方式获取 IP (原 get_local_ip)
+fn get_local_ip_udp() -> Option<String> {
+    // 这是一个同步调用，我们在 async 上下文中不能直接用?转换 async 结果
+    // 这里简单起见，既然 scan_local_network 是 async 的，我们可以稍微从简
+    // 但为了不引入 complex async block for now, use std::net if possible or just assume this is rare fallback
+    // Use std::net::UdpSocket for synchronous check
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip().to_string())
+}
+
+
+/// 解析网段 (`a.b.c.d/n`) 或单个 IP，返回其中可用的主机地址
+///
+/// /31 和 /32 以外的网段会排除网络地址和广播地址。
+pub fn parse_cidr(cidr: &str) -> Result<Vec<Ipv4Addr>> {
+    let (ip, prefix) = match cidr.trim().split_once('/') {
+        Some((ip, prefix)) => (ip, prefix.parse::<u32>().with_context(|| format!("无效的网段: {}", cidr))?),
+        None => (cidr.trim(), 32),
+    };
+    let ip: Ipv4Addr = ip.parse().with_context(|| format!("无效的网段: {}", cidr))?;
+    if prefix > 32 {
+        bail!("无效的网段: {}", cidr);
+    }
+    if prefix < 16 {
+        bail!("网段过大: {} (最大 /16)", cidr);
+    }
+
+    let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+    let network = u32::from(ip) & mask;
+    let broadcast = network | !mask;
+    let hosts = if prefix >= 31 {
+        network..=broadcast
+    } else {
+        (network + 1)..=(broadcast - 1)
+    };
+    Ok(hosts.map(Ipv4Addr::from).collect())
+}
+
+/// 找到与目标同一 /24 网段的本机地址，用于绑定网卡
+fn bind_ip_for(target: Ipv4Addr, local_ips: &[Ipv4Addr]) -> Option<Ipv4Addr> {
+    local_ips
+        .iter()
+        .find(|local| local.octets()[..3] == target.octets()[..3])
+        .copied()
+}
+
+/// 计算扫描目标 (地址, 绑定的本机地址)
+fn scan_targets(options: &ScanOptions, local_ips: &[Ipv4Addr]) -> Result<Vec<(Ipv4Addr, Option<Ipv4Addr>)>> {
+    let mut hosts: Vec<Ipv4Addr> = Vec::new();
+    if options.cidrs.is_empty() {
+        // 假设是 /24 子网
+        for local_ip in local_ips {
+            let [a, b, c, _] = local_ip.octets();
+            hosts.extend(parse_cidr(&format!("{}.{}.{}.0/24", a, b, c))?);
+        }
+    } else {
+        for cidr in &options.cidrs {
+            hosts.extend(parse_cidr(cidr)?);
+        }
+    }
+
+    hosts.sort();
+    hosts.dedup();
+    hosts.retain(|ip| !local_ips.contains(ip));
+    if hosts.len() * options.ports.len().max(1) > MAX_TARGETS {
+        bail!("扫描目标过多 ({} 个地址 × {} 个端口)", hosts.len(), options.ports.len());
+    }
+    Ok(hosts.into_iter().map(|ip| (ip, bind_ip_for(ip, local_ips))).collect())
+}
+
+/// 按完整地址 (逐段数值) 和端口排序
+pub fn sort_results(results: &mut [ScanResult]) {
+    results.sort_by(|a, b| {
+        let key = |r: &ScanResult| (r.ip.parse::<IpAddr>().ok(), r.port);
+        key(a).cmp(&key(b)).then_with(|| a.ip.cmp(&b.ip))
+    });
+}
+
+/// 连接并用 ADB 握手验证一个目标
+async fn probe_target(
+    target: SocketAddr,
+    bind_ip: Option<Ipv4Addr>,
+    timeout: Duration,
+    key: Option<Arc<AdbKey>>,
+) -> Option<ScanResult> {
+    // 使用 TcpSocket 绑定本地 IP，这可以强制流量走正确的物理网卡，
+    // 从而绕过 V2Ray/Tun 模式的全局流量劫持 (因为 Tun 通常无法劫持绑定了特定物理 IP 的流量)
+    let socket = tokio::net::TcpSocket::new_v4().ok()?;
+    if let Some(bind_ip) = bind_ip {
+        socket.bind(SocketAddr::new(bind_ip.into(), 0)).ok()?;
+    }
+
+    let mut stream = match tokio::time::timeout(timeout, socket.connect(target)).await {
+        Ok(Ok(stream)) => stream,
+        _ => return None,
+    };
+
+    // ADB 握手验证
+    let probe = adb_transport::probe(&mut stream, key.as_deref());
+    match tokio::time::timeout(PROBE_TIMEOUT, probe).await {
+        Ok(Ok(result)) => Some(ScanResult::from_probe(target.ip().to_string(), target.port(), result)),
+        _ => None,
+    }
+}
+
+/// 收集扫描结果，新发现的设备立即推送事件
+struct Collector<R: Runtime> {
+    app: AppHandle<R>,
+    results: Mutex<Vec<ScanResult>>,
+}
+
+impl<R: Runtime> Collector<R> {
+    fn add(&self, result: ScanResult) {
+        let mut results = self.results.lock().unwrap();
+        // 去重
+        if results.iter().any(|r| r.ip == result.ip && r.port == result.port) {
+            return;
+        }
+        let _ = self.app.emit("scan-found", &result);
+        results.push(result);
+    }
+}
+
+/// 取消当前的扫描
+pub fn cancel() -> bool {
+    match CURRENT.lock().unwrap().take() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 扫描局域网设备，返回按地址排序的全部结果
+///
+/// 会取消上一个尚未结束的扫描。被取消时返回已发现的设备。
+pub async fn scan<R: Runtime>(app: AppHandle<R>, options: ScanOptions) -> Result<Vec<ScanResult>> {
+    if options.ports.is_empty() && !options.mdns {
+        bail!("没有要扫描的端口");
+    }
+    let local_ips: Vec<Ipv4Addr> = get_all_local_ips().iter().filter_map(|ip| ip.parse().ok()).collect();
+    let targets = if options.ports.is_empty() { Vec::new() } else { scan_targets(&options, &local_ips)? };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = CURRENT.lock().unwrap().replace(cancel.clone()) {
+        previous.store(true, Ordering::Relaxed);
+    }
+    let collector = Arc::new(Collector { app: app.clone(), results: Mutex::new(Vec::new()) });
+
+    let mdns = options.mdns.then(|| {
+        let cancel = cancel.clone();
+        let collector = collector.clone();
+        tokio::task::spawn_blocking(move || {
+            adb_mdns::browse(MDNS_BROWSE_TIMEOUT, &cancel, |service| collector.add(ScanResult::from_mdns(service)))
+        })
+    });
+
+    // 用当前密钥签名，已授权的设备可以直接拿到型号
+    let key = tokio::task::spawn_blocking(|| AdbKey::load_active().ok())
+        .await
+        .ok()
+        .flatten()
+        .map(Arc::new);
+
+    let timeout = Duration::from_millis(options.timeout_ms.max(1));
+    let semaphore = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    'targets: for (ip, bind_ip) in targets {
+        for &port in &options.ports {
+            let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                break 'targets;
+            };
+            if cancel.load(Ordering::Relaxed) {
+                break 'targets;
+            }
+            let (key, collector) = (key.clone(), collector.clone());
+            tasks.spawn(async move {
+                if let Some(result) = probe_target(SocketAddr::new(ip.into(), port), bind_ip, timeout, key).await {
+                    collector.add(result);
+                }
+                drop(permit);
+            });
+            // 回收已完成的任务
+            while tasks.try_join_next().is_some() {}
+        }
+    }
+    if cancel.load(Ordering::Relaxed) {
+        tasks.abort_all();
+    }
+    while tasks.join_next().await.is_some() {}
+
+    if let Some(mdns) = mdns {
+        match mdns.await {
+            Ok(Err(e)) => eprintln!("mDNS 发现失败: {:#}", e),
+            Ok(Ok(services)) => {
+                // 浏览期间被移除的服务不保留
+                collector.results.lock().unwrap().retain(|r| {
+                    r.service.is_none() || services.iter().any(|s| s.ip == r.ip && s.port == r.port)
+                });
+            }
+            Err(_) => {}
+        }
+    }
+
+    let cancelled = cancel.load(Ordering::Relaxed);
+    {
+        let mut current = CURRENT.lock().unwrap();
+        if current.as_ref().is_some_and(|c| Arc::ptr_eq(c, &cancel)) {
+            *current = None;
+        }
+    }
+
+    let mut results = std::mem::take(&mut *collector.results.lock().unwrap());
+    sort_results(&mut results);
+    let _ = app.emit("scan-finished", ScanFinished { count: results.len(), cancelled });
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cidr() {
+        let hosts = parse_cidr("192.168.1.0/24").unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(hosts[253], Ipv4Addr::new(192, 168, 1, 254));
+
+        assert_eq!(parse_cidr("10.0.0.7").unwrap(), vec![Ipv4Addr::new(10, 0, 0, 7)]);
+        assert_eq!(parse_cidr("10.0.0.5/30").unwrap().len(), 2);
+        assert!(parse_cidr("10.0.0.0/8").is_err());
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+        assert!(parse_cidr("printer").is_err());
+
+        let local = [Ipv4Addr::new(192, 168, 1, 10)];
+        let options = ScanOptions { cidrs: vec!["192.168.1.8/30".into()], ..Default::default() };
+        let targets = scan_targets(&options, &local).unwrap();
+        assert_eq!(targets, vec![(Ipv4Addr::new(192, 168, 1, 9), Some(local[0]))]);
+    }
+
+    #[test]
+    fn test_sort_results() {
+        let result = |ip: &str, port| ScanResult {
+            ip: ip.into(),
+            port,
+            service: None,
+            host: None,
+            pairing: false,
+            model: None,
+            product: None,
+            auth_required: false,
+        };
+        let mut results = vec![
+            result("192.168.2.1", 5555),
+            result("192.168.1.20", 5555),
+            result("10.0.0.3", 5555),
+            result("192.168.1.20", 37000),
+            result("192.168.1.3", 5555),
+        ];
+        sort_results(&mut results);
+        let order: Vec<_> = results.iter().map(|r| format!("{}:{}", r.ip, r.port)).collect();
+        assert_eq!(order, [
+            "10.0.0.3:5555",
+            "192.168.1.3:5555",
+            "192.168.1.20:5555",
+            "192.168.1.20:37000",
+            "192.168.2.1:5555",
+        ]);
+    }
+}
@@ -5,3 +5,20 @@ pub mod scrcpy;
 pub mod tray;
 pub mod commands;
 pub mod adb_sync;
+pub mod adb_host;
+pub mod adb_shell;
+pub mod adb_install;
+pub mod adb_forward;
+pub mod adb_key;
+pub mod adb_transport;
+pub mod adb_mdns;
+pub mod adb_pair;
+pub mod lan_scan;
+pub mod binaries;
+pub mod session;
+pub mod scrcpy_args;
+pub mod scrcpy_compat;
+pub mod apk_bundle;
+pub mod device_tracker;
+pub mod adb_compress;
+pub mod transfer;
@@ -4,26 +4,41 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use scrcpy_launcher_lib::{commands, tray};
+use scrcpy_launcher_lib::{commands, config::Config, device_tracker, session::SessionManager, transfer::TransferManager, tray};
 use tauri::Manager;
 
 fn main() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            // 当尝试打开第二个实例时，显示主窗口
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
-                let _ = window.set_focus();
-            }
-        }))
+        .plugin(tauri_plugin_fs::init());
+
+    // 单实例插件只在 Windows 上依赖
+    #[cfg(windows)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+        // 当尝试打开第二个实例时，显示主窗口
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }));
+
+    builder
         .setup(|app| {
+            // 文件传输管理器
+            let config = Config::load();
+            app.manage(TransferManager::new(config.transfer_concurrency, config.transfer_max_retries));
+
+            // scrcpy 会话管理器
+            app.manage(SessionManager::new());
+
             // 设置系统托盘
             if let Err(e) = tray::setup_tray(app) {
                 eprintln!("Failed to setup tray: {}", e);
             }
+
+            // 后台跟踪设备插拔和授权状态
+            device_tracker::start(app.handle().clone());
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -37,14 +52,33 @@ fn main() {
             commands::get_config,
             commands::save_config,
             commands::check_adb_status,
+            commands::adb_devices,
+            commands::adb_server_version,
+            commands::adb_servers,
+            commands::adb_server_add,
+            commands::adb_server_remove,
+            commands::adb_shell,
+            commands::set_active_device,
+            commands::forward_list,
+            commands::forward_add,
+            commands::forward_remove,
+            commands::set_device_backend,
+            commands::adb_key_list,
+            commands::adb_key_generate,
+            commands::adb_key_import,
+            commands::adb_key_export,
+            commands::adb_key_select,
             commands::connect_wireless,
             commands::enable_tcpip,
             commands::disconnect_all,
             commands::get_installed_apps,
             commands::kill_scrcpy,
+            commands::session_list,
+            commands::session_stop,
             commands::launch_mirror,
             commands::launch_audio,
             commands::launch_app,
+            commands::preview_launch,
             commands::add_adb_history,
             commands::save_app,
             commands::delete_app,
@@ -55,12 +89,34 @@ fn main() {
             commands::set_first_run_complete,
             commands::save_global_settings,
             commands::save_scrcpy_options,
+            commands::import_scrcpy_args,
+            commands::export_scrcpy_args,
+            commands::check_scrcpy_compat,
             commands::save_tray_settings,
             commands::save_first_run_config,
             commands::set_theme,
             commands::adb_push_file,
+            commands::adb_pull_file,
+            commands::adb_install_apk,
+            commands::transfer_push,
+            commands::transfer_pull,
+            commands::transfer_cancel,
+            commands::transfer_list,
+            commands::transfer_history,
+            commands::transfer_clear_history,
+            commands::save_transfer_settings,
+            commands::adb_stat,
+            commands::adb_list_dir,
+            commands::adb_delete,
+            commands::adb_mkdir,
+            commands::adb_rename,
             commands::pair_device,
+            commands::qr_pair_start,
+            commands::qr_pair_cancel,
             commands::scan_tcp_devices,
+            commands::scan_cancel,
+            commands::binary_status,
+            commands::set_binary_path,
             commands::get_custom_icons_dir,
             commands::open_custom_icons_dir,
             commands::get_app_icon_data,
@@ -9,7 +9,17 @@ use std::process::{Command, Stdio};
 use std::path::PathBuf;
 use std::io::BufReader;
 use crate::config::{Config, ScrcpyOptions};
-use tauri::Emitter;
+use crate::adb_host::{self, AdbDevice, AdbHost, AdbServer, DeviceState, LOCAL_SERVER};
+use crate::adb_shell::AdbShell;
+use crate::adb_key;
+use crate::binaries::{self, Tool};
+use crate::adb_transport::{self, AdbBackend};
+use crate::session::SessionManager;
+use crate::device_tracker;
+use crate::scrcpy_args;
+use crate::scrcpy_compat::{self, CompatReport};
+use indexmap::IndexMap;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
 use std::time::Duration;
 
 
@@ -20,29 +30,14 @@ use std::os::windows::process::CommandExt;
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
-/// 获取资源目录路径
-fn get_resources_path() -> PathBuf {
-    // 在开发模式下使用相对路径，打包后使用资源目录
-    if cfg!(debug_assertions) {
-        PathBuf::from("resources")
-    } else {
-        // 获取可执行文件所在目录
-        std::env::current_exe()
-            .ok()
-            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-            .map(|p| p.join("resources"))
-            .unwrap_or_else(|| PathBuf::from("resources"))
-    }
-}
-
 /// 获取 ADB 可执行文件路径
 fn get_adb_path() -> PathBuf {
-    get_resources_path().join("bin").join("adb.exe")
+    binaries::path(Tool::Adb)
 }
 
 /// 获取 Scrcpy 可执行文件路径
 fn get_scrcpy_path() -> PathBuf {
-    get_resources_path().join("bin").join("scrcpy.exe")
+    binaries::path(Tool::Scrcpy)
 }
 
 /// ADB 状态检查结果
@@ -50,13 +45,21 @@ fn get_scrcpy_path() -> PathBuf {
 pub struct AdbStatus {
     pub connected: bool,
     pub message: String,
+    /// ADB Server 报告的全部设备 (包括未授权/离线的)
+    pub devices: Vec<AdbDevice>,
 }
 
 /// 创建命令并配置为无窗口模式（仅 Windows）
+///
+/// 选中团队密钥时通过 `ADB_VENDOR_KEYS` 传给 adb (scrcpy 启动的 adb 同样继承)，
+/// 已在运行的 ADB Server 需要重启后才会使用。
 fn create_command(program: &PathBuf) -> Command {
     let mut cmd = Command::new(program);
     #[cfg(windows)]
     cmd.creation_flags(CREATE_NO_WINDOW);
+    if let Some(keys) = adb_key::vendor_keys() {
+        cmd.env("ADB_VENDOR_KEYS", keys);
+    }
     cmd
 }
 
@@ -68,46 +71,144 @@ fn create_command_str(program: &str) -> Command {
     cmd
 }
 
-/// 检查 ADB 连接状态
-pub fn check_adb_status() -> AdbStatus {
-    let adb_path = get_adb_path();
-    
-    let output = create_command(&adb_path)
-        .arg("devices")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output();
+/// 创建连接到指定 ADB Server 的 adb 命令 (-H/-P)
+fn adb_command(server: &AdbServer) -> Command {
+    let mut cmd = create_command(&get_adb_path());
+    cmd.args(server.adb_args());
+    cmd
+}
 
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let lines: Vec<&str> = stdout.lines().collect();
-            
-            // 跳过第一行 "List of devices attached"
-            let devices: Vec<&str> = lines
-                .iter()
-                .skip(1)
-                .filter(|line| line.contains("device") && !line.contains("offline"))
-                .copied()
-                .collect();
-
-            if !devices.is_empty() {
-                let device_id = devices[0].split_whitespace().next().unwrap_or("unknown");
-                AdbStatus {
-                    connected: true,
-                    message: format!("已连接: {}", device_id),
-                }
-            } else {
-                AdbStatus {
-                    connected: false,
-                    message: "未连接设备".to_string(),
-                }
-            }
+/// 获取本机 ADB Server 的设备列表，Server 未运行时先启动它
+fn list_local_devices() -> Result<Vec<AdbDevice>, String> {
+    let host = AdbHost::default();
+    if let Ok(devices) = host.devices() {
+        return Ok(devices);
+    }
+
+    // 连接失败通常是 Server 未启动 (adb devices 会自动启动，原生客户端需要手动启动)
+    adb_command(&AdbServer::local())
+        .arg("start-server")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|_| "未找到ADB命令".to_string())?;
+
+    host.devices().map_err(|e| format!("ADB 服务不可用: {}", e))
+}
+
+/// 获取全部 ADB Server 的设备列表，并记录本机设备
+///
+/// 本机 Server 实时查询；远程 Server 使用设备跟踪线程保存的列表 (见 `device_tracker::remote_devices`)，
+/// 不可达的远程 Server 不会拖慢查询。同一序列号出现在多个 Server 时以先出现的为准。
+pub fn list_devices() -> Result<Vec<AdbDevice>, String> {
+    let mut devices = list_local_devices()?;
+    adb_host::record_devices(LOCAL_SERVER, &devices);
+
+    for device in device_tracker::remote_devices() {
+        if !devices.iter().any(|d| d.serial == device.serial) {
+            devices.push(device);
         }
-        Err(_) => AdbStatus {
+    }
+    Ok(devices)
+}
+
+/// 没有可用设备时的提示 (区分未授权、离线和未连接)
+fn no_device_message(devices: &[AdbDevice]) -> String {
+    if let Some(d) = devices.iter().find(|d| d.state == DeviceState::Unauthorized) {
+        format!("设备未授权，请在手机上允许 USB 调试: {}", d.serial)
+    } else if let Some(d) = devices.iter().find(|d| d.state == DeviceState::Offline) {
+        format!("设备离线: {}", d.serial)
+    } else {
+        "未连接设备".to_string()
+    }
+}
+
+/// 从设备列表中选出要操作的设备
+///
+/// 优先使用显式指定的序列号，其次是当前设备 (`Config.active_device`)；
+/// 都没有时只在恰好连接了一台设备时自动选择。
+fn pick_device(serial: Option<&str>, active: Option<&str>, devices: &[AdbDevice]) -> Result<String, String> {
+    if let Some(serial) = serial {
+        return match devices.iter().find(|d| d.serial == serial) {
+            Some(d) if d.is_ready() => Ok(d.serial.clone()),
+            Some(d) => Err(format!("设备不可用 ({:?}): {}", d.state, serial)),
+            None => Err(format!("设备未连接: {}", serial)),
+        };
+    }
+
+    let ready: Vec<&AdbDevice> = devices.iter().filter(|d| d.is_ready()).collect();
+    if let Some(active) = active.and_then(|a| ready.iter().find(|d| d.serial == a)) {
+        return Ok(active.serial.clone());
+    }
+
+    match ready.as_slice() {
+        [device] => Ok(device.serial.clone()),
+        [] => Err(no_device_message(devices)),
+        _ => Err(format!(
+            "已连接多台设备 ({})，请先选择要使用的设备",
+            ready.iter().map(|d| d.serial.as_str()).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// 确定要操作的设备序列号，规则见 `pick_device`
+///
+/// 设置为直连的设备不经过 ADB Server，不会出现在设备列表中，指定或选中时先确认直连可用。
+pub fn resolve_device(serial: Option<&str>) -> Result<String, String> {
+    let config = Config::load();
+    if let Some(direct) = direct_device(&config, serial) {
+        adb_transport::connection(direct).map_err(|e| format!("无法直连设备 {}: {:#}", direct, e))?;
+        return Ok(direct.to_string());
+    }
+
+    let devices = list_devices()?;
+    pick_device(serial, config.active_device.as_deref(), &devices)
+}
+
+/// 在阻塞线程中确定设备 (直连设备可能要等待握手和授权确认)
+pub async fn resolve_device_async(serial: Option<String>) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || resolve_device(serial.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 指定或选中的设备设置为直连时返回其序列号
+fn direct_device<'a>(config: &'a Config, serial: Option<&'a str>) -> Option<&'a str> {
+    serial
+        .or(config.active_device.as_deref())
+        .filter(|s| config.device_backends.get(*s) == Some(&AdbBackend::Direct))
+}
+
+/// 设置当前设备 (None 表示清除选择)
+pub fn set_active_device(serial: Option<String>) {
+    let mut config = Config::load();
+    config.active_device = serial;
+    config.save();
+}
+
+/// 检查 ADB 连接状态
+pub fn check_adb_status() -> AdbStatus {
+    let devices = match list_devices() {
+        Ok(devices) => devices,
+        Err(message) => return AdbStatus {
             connected: false,
-            message: "未找到ADB命令".to_string(),
+            message,
+            devices: Vec::new(),
         },
+    };
+
+    let ready = devices.iter().filter(|d| d.is_ready()).count();
+    let message = match (ready, pick_device(None, Config::load().active_device.as_deref(), &devices)) {
+        (0, _) => no_device_message(&devices),
+        (1, Ok(serial)) => format!("已连接: {}", serial),
+        (n, Ok(serial)) => format!("已连接: {} (共 {} 台设备)", serial, n),
+        (n, Err(_)) => format!("已连接 {} 台设备，请选择要使用的设备", n),
+    };
+
+    AdbStatus {
+        connected: ready > 0,
+        message,
+        devices,
     }
 }
 
@@ -121,8 +222,6 @@ pub struct CommandResult {
 /// 无线连接到设备
 /// 无线连接到设备
 pub async fn connect_wireless(ip: &str) -> CommandResult {
-    let adb_path = get_adb_path();
-    
     // 保存到历史记录
     let mut config = Config::load();
     config.add_adb_history(ip);
@@ -167,7 +266,7 @@ pub async fn connect_wireless(ip: &str) -> CommandResult {
     }
 
     // 2. 执行真正的 adb connect
-    let output = create_command(&adb_path)
+    let output = adb_command(&AdbServer::local())
         .args(["connect", ip])
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -197,10 +296,8 @@ pub async fn connect_wireless(ip: &str) -> CommandResult {
 
 /// 无线配对设备 (Android 11+)
 pub fn pair_device(addr: &str, code: &str) -> CommandResult {
-    let adb_path = get_adb_path();
-    
     // adb pair <ip>:<port> <code>
-    let output = create_command(&adb_path)
+    let output = adb_command(&AdbServer::local())
         .args(["pair", addr, code])
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -232,44 +329,35 @@ pub fn pair_device(addr: &str, code: &str) -> CommandResult {
 }
 
 /// 启用 TCP/IP 模式
-pub fn enable_tcpip_mode() -> CommandResult {
-    let adb_path = get_adb_path();
-
-    let output = create_command(&adb_path)
-        .args(["tcpip", "5555"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output();
+pub fn enable_tcpip_mode(serial: Option<&str>) -> CommandResult {
+    let serial = match resolve_device(serial) {
+        Ok(serial) => serial,
+        Err(message) => return CommandResult {
+            success: false,
+            message: format!("失败: {}", message),
+        },
+    };
 
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            
-            if stdout.contains("restarting in TCP mode") || output.status.success() {
-                CommandResult {
-                    success: true,
-                    message: "已开启无线调试 (端口 5555)，请断开USB线并输入IP连接。".to_string(),
-                }
-            } else {
-                CommandResult {
-                    success: false,
-                    message: format!("失败: {}", if stderr.is_empty() { "未连接设备" } else { &stderr }),
-                }
-            }
-        }
+    // 直接请求设备的 tcpip 服务，设备成功时应答 "restarting in TCP mode port: 5555"
+    match AdbShell::new(Some(serial)).service("tcpip:5555") {
+        Ok(reply) if reply.starts_with("restarting") => CommandResult {
+            success: true,
+            message: "已开启无线调试 (端口 5555)，请断开USB线并输入IP连接。".to_string(),
+        },
+        Ok(reply) => CommandResult {
+            success: false,
+            message: format!("失败: {}", reply.trim()),
+        },
         Err(e) => CommandResult {
             success: false,
-            message: format!("执行失败: {}", e),
+            message: format!("失败: {:#}", e),
         },
     }
 }
 
 /// 断开所有设备连接
 pub fn disconnect_all() -> CommandResult {
-    let adb_path = get_adb_path();
-
-    let output = create_command(&adb_path)
+    let output = adb_command(&AdbServer::local())
         .arg("disconnect")
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -298,184 +386,18 @@ pub fn disconnect_all() -> CommandResult {
     }
 }
 
-/// 通过 ipconfig 获取所有本机 IPv4 地址，并过滤掉虚拟网卡
-fn get_all_local_ips() -> Vec<String> {
-    let output = std::process::Command::new("ipconfig")
-        .output()
-        .ok();
-
-    let mut ips = Vec::new();
-
-    if let Some(output) = output {
-        // Windows 的 ipconfig 输出编码通常是 GBK (中文环境)，但 `String::from_utf8_lossy` 处理 GBK 会乱码。
-        // 不过我们主要匹配 "IPv4" (ASCII) 和 ":", 以及 IP 数字。
-        // 适配器名称如果是中文可能会乱码，导致过滤失效。
-        // 这是一个潜在风险点。但通常 Virtual/VMware 等关键词是英文。
-        // "vEthernet" 也是英文。
-        // 如果能检测到 "Virtual", "Pseudo", "VMware", "Box", "VPN" 等关键词最好。
-        
-        // 稍微优化：尝试 decode GBK 最好，但引入依赖麻烦。
-        // 这里假设关键的虚拟网卡标识通常包含英文部分，或者通过 IP 特征辅助过滤。
-        
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut current_adapter = String::new();
-
-        for line in stdout.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            // 适配器行通常以 ":" 结尾，且不包含 " . . ."
-            // 例如 "Ethernet adapter Ethernet:" 或 "以太网适配器 vEthernet (WSL):"
-            if line.ends_with(':') && !line.contains(". . .") {
-                current_adapter = line.to_lowercase();
-                continue;
-            }
-
-            // 匹配 IPv4
-            if line.contains("IPv4") && line.contains(":") {
-                // 检查当前适配器是否应该被忽略
-                if current_adapter.contains("vmware") 
-                    || current_adapter.contains("virtual") 
-                    || current_adapter.contains("vethernet") // Hyper-V / WSL
-                    || current_adapter.contains("pseudo")
-                    || current_adapter.contains("tap-windows")
-                    || current_adapter.contains("vpn")
-                    || current_adapter.contains("tun")
-                    || current_adapter.contains("singbox")
-                    || current_adapter.contains("wsl")
-                {
-                    continue;
-                }
-
-                let parts: Vec<&str> = line.split(':').collect();
-                if let Some(ip_part) = parts.last() {
-                    let ip = ip_part.trim().to_string();
-                    
-                    // 过滤 IP 特征
-                    if ip == "127.0.0.1" { continue; }
-                    
-                    // 过滤常见的 TUN/Fake IP 网段
-                    // 198.18.0.0/15 是保留用于性能测试的，常被 TUN 模式用来做 Fake IP
-                    if ip.starts_with("198.18.") { continue; }
-                    
-                    // 169.254.x.x (APIPA)
-                    if ip.starts_with("169.254.") { continue; }
-
-                    if ip.split('.').count() == 4 {
-                        ips.push(ip);
-                    }
-                }
-            }
-        }
-    }
-    
-    // 兜底：如果过滤太严格导致没 IP 了，尝试 UDP 方式（至少能拿到一个出网 IP）
-    if ips.is_empty() {
-         if let Some(ip) = get_local_ip_udp() {
-             // 再次检查 UDP 拿到的 IP 是否也是 TUN IP
-             if !ip.starts_with("198.18.") {
-                 ips.push(ip);
-             }
-         }
-    }
-
-    ips
-}
-
-/// UDP 方式获取 IP (原 get_local_ip)
-fn get_local_ip_udp() -> Option<String> {
-    // 这是一个同步调用，我们在 async 上下文中不能直接用?转换 async 结果
-    // 这里简单起见，既然 scan_local_network 是 async 的，我们可以稍微从简
-    // 但为了不引入 complex async block for now, use std::net if possible or just assume this is rare fallback
-    // Use std::net::UdpSocket for synchronous check
-    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
-    socket.connect("8.8.8.8:80").ok()?;
-    socket.local_addr().ok().map(|addr| addr.ip().to_string())
-}
-
-/// 扫描局域网内开放 5555 端口的设备
-pub async fn scan_local_network() -> Vec<String> {
-    let local_ips = get_all_local_ips();
-    let mut tasks = Vec::new();
-
-    // 针对每个找到的本地 IP 所在的网段进行扫描
-    for local_ip in local_ips {
-        let parts: Vec<&str> = local_ip.split('.').collect();
-        if parts.len() != 4 {
-            continue;
-        }
-
-        // 假设是 /24 子网
-        let prefix = format!("{}.{}.{}.", parts[0], parts[1], parts[2]);
-        
-        for i in 1..255 {
-            let ip = format!("{}{}", prefix, i);
-            if ip == local_ip {
-                continue;
-            }
-
-            let bind_ip = local_ip.clone();
-
-            tasks.push(tokio::spawn(async move {
-                let target_addr: std::net::SocketAddr = format!("{}:5555", ip).parse().ok()?;
-                let bind_addr: std::net::SocketAddr = format!("{}:0", bind_ip).parse().ok()?;
-                
-                // 使用 TcpSocket 绑定本地 IP，这可以强制流量走正确的物理网卡，
-                // 从而绕过 V2Ray/Tun 模式的全局流量劫持 (因为 Tun 通常无法劫持绑定了特定物理 IP 的流量)
-                let socket = tokio::net::TcpSocket::new_v4().ok()?;
-                socket.bind(bind_addr).ok()?;
-
-                // 缩短超时时间以加快多网段扫描速度
-                match tokio::time::timeout(Duration::from_millis(150), socket.connect(target_addr)).await {
-                    Ok(Ok(_)) => Some(ip),
-                    _ => None,
-                }
-            }));
-        }
-    }
-
-    let mut devices = Vec::new();
-    for task in tasks {
-        if let Ok(Some(ip)) = task.await {
-            // 去重
-            if !devices.contains(&ip) {
-                devices.push(ip);
-            }
-        }
-    }
-    
-    // 排序
-    devices.sort_by(|a, b| {
-        let a_parts: Vec<&str> = a.split('.').collect();
-        let b_parts: Vec<&str> = b.split('.').collect();
-        // 简单按最后一段排序，不够严谨但够用
-        let a_last: u8 = a_parts.last().unwrap_or(&"0").parse().unwrap_or(0);
-        let b_last: u8 = b_parts.last().unwrap_or(&"0").parse().unwrap_or(0);
-        a_last.cmp(&b_last)
-    });
-
-    devices
-}
-
 /// 获取已安装的第三方应用列表
-pub fn get_installed_apps() -> Result<Vec<String>, String> {
-    let adb_path = get_adb_path();
-
-    let output = create_command(&adb_path)
-        .args(["shell", "pm", "list", "packages", "-3"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("执行失败: {}", e))?;
-
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+pub fn get_installed_apps(serial: Option<&str>) -> Result<Vec<String>, String> {
+    let serial = resolve_device(serial)?;
+    let output = AdbShell::new(Some(serial))
+        .run("pm list packages -3")
+        .map_err(|e| format!("执行失败: {:#}", e))?;
+
+    if !output.success() {
+        return Err(output.error_message());
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let packages: Vec<String> = stdout
+    let packages: Vec<String> = output.stdout
         .lines()
         .map(|line| line.replace("package:", "").trim().to_string())
         .filter(|s| !s.is_empty())
@@ -484,51 +406,30 @@ pub fn get_installed_apps() -> Result<Vec<String>, String> {
     Ok(packages)
 }
 
-/// 关闭所有 scrcpy 进程
-pub fn kill_scrcpy_processes() -> CommandResult {
-    let output = create_command_str("taskkill")
-        .args(["/F", "/IM", "scrcpy.exe"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output();
-
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            // Windows 中文版输出
-            if stdout.contains("成功") || stdout.contains("SUCCESS") {
-                CommandResult {
-                    success: true,
-                    message: "已关闭所有 Scrcpy 窗口。".to_string(),
-                }
-            } else if stdout.contains("没有找到") || stdout.contains("not found") {
-                CommandResult {
-                    success: true,
-                    message: "当前没有正在运行的 Scrcpy 窗口。".to_string(),
-                }
-            } else {
-                CommandResult {
-                    success: true,
-                    message: "关闭指令已发送。".to_string(),
-                }
-            }
-        }
-        Err(e) => CommandResult {
-            success: false,
-            message: format!("执行失败: {}", e),
+/// 关闭启动器启动的所有 scrcpy 进程 (不影响其他方式启动的 scrcpy)
+pub fn kill_scrcpy_processes<R: Runtime>(app: &AppHandle<R>) -> CommandResult {
+    match app.state::<SessionManager>().stop_all() {
+        0 => CommandResult {
+            success: true,
+            message: "当前没有正在运行的 Scrcpy 窗口。".to_string(),
+        },
+        count => CommandResult {
+            success: true,
+            message: format!("已关闭 {} 个 Scrcpy 窗口。", count),
         },
     }
 }
 
 /// 关闭 ADB 服务器
 pub fn kill_adb_server() -> CommandResult {
-    let adb_path = get_adb_path();
-    
-    let _ = create_command(&adb_path)
-        .arg("kill-server")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .output();
+    // 优先通过 host:kill 关闭，失败时再调用命令行
+    if AdbHost::default().kill().is_err() {
+        let _ = adb_command(&AdbServer::local())
+            .arg("kill-server")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .output();
+    }
 
     let _ = create_command_str("taskkill")
         .args(["/F", "/IM", "adb.exe"])
@@ -543,18 +444,16 @@ pub fn kill_adb_server() -> CommandResult {
 }
 
 /// 退出前清理
-pub fn cleanup_before_exit() {
-    kill_scrcpy_processes();
+pub fn cleanup_before_exit<R: Runtime>(app: &AppHandle<R>) {
+    kill_scrcpy_processes(app);
     kill_adb_server();
 }
 
 /// 发送文件到设备 (带进度)
 pub fn push_file<R: tauri::Runtime>(window: &tauri::Window<R>, local_path: &str, remote_path: &str) -> CommandResult {
-    let adb_path = get_adb_path();
-
     // 尝试启动 adb push 进程
     // -p: 显示进度 (即使重定向输出也能强制显示)
-    let mut child = match create_command(&adb_path)
+    let mut child = match adb_command(&AdbServer::local())
         .args(["push", "-p", local_path, remote_path])
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -639,122 +538,247 @@ pub fn push_file<R: tauri::Runtime>(window: &tauri::Window<R>, local_path: &str,
 
 /// 从选项构建参数列表
 fn build_args_from_options(opts: &ScrcpyOptions) -> Vec<String> {
-    let mut args = Vec::new();
-    
-    if opts.hid_keyboard {
-        args.push("-K".to_string());
-    }
-    if opts.stay_awake {
-        args.push("--stay-awake".to_string());
-    }
-    if opts.turn_screen_off {
-        args.push("-S".to_string());
-    }
-    if opts.show_touches {
-        args.push("--show-touches".to_string());
-    }
-    if opts.always_on_top {
-        args.push("--always-on-top".to_string());
-    }
-    if opts.borderless {
-        args.push("--window-borderless".to_string());
-    }
-    if opts.power_off_on_close {
-        args.push("--power-off-on-close".to_string());
-    }
-    if opts.local_ime {
-        args.push("--display-ime-policy=local".to_string());
-    }
-    if opts.max_size > 0 {
-        args.push(format!("-m {}", opts.max_size));
-    }
-    if opts.max_fps > 0 {
-        args.push(format!("--max-fps={}", opts.max_fps));
-    }
-    
-    args
+    scrcpy_args::to_args(opts)
 }
 
 /// 启动模式
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum LaunchMode {
     Mirror,
     Audio,
     App { package: String, settings: Option<crate::config::AppSettings>, scrcpy_args: Option<String> },
 }
 
-/// 构建并运行 scrcpy 命令
-pub fn launch_scrcpy(mode: LaunchMode) -> CommandResult {
-    // 1. 预检查：确保有设备连接
-    let adb_status = check_adb_status();
-    if !adb_status.connected {
-        return CommandResult {
-            success: false,
-            message: format!("启动失败: {}", adb_status.message),
-        };
+impl LaunchMode {
+    /// 会话记录中的模式名
+    fn name(&self) -> &'static str {
+        match self {
+            LaunchMode::Mirror => "mirror",
+            LaunchMode::Audio => "audio",
+            LaunchMode::App { .. } => "app",
+        }
     }
 
+    fn package(&self) -> Option<&str> {
+        match self {
+            LaunchMode::App { package, .. } => Some(package),
+            _ => None,
+        }
+    }
+}
+
+/// 参数来自哪一层配置
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgSource {
+    /// 目标设备 (--serial)
+    Device,
+    /// 设备所在的远程 ADB Server
+    AdbServer,
+    /// 全局选项 `scrcpy_options`
+    ScrcpyOptions,
+    /// 全局自定义参数 `custom_args`
+    CustomArgs,
+    /// 应用流转选项 `app_stream_options`
+    AppStreamOptions,
+    /// 应用流转自定义参数 `app_custom_args`
+    AppCustomArgs,
+    /// 应用专属参数 `AppConfig.scrcpy_args`
+    AppScrcpyArgs,
+    /// 全局显示设置 `global_settings` (分辨率、DPI)
+    GlobalSettings,
+    /// 应用专属显示设置 `AppConfig.settings`
+    AppSettings,
+    /// 启动模式固定添加的参数
+    Mode,
+}
+
+/// 一个参数及其来源
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct LaunchArg {
+    pub arg: String,
+    pub source: ArgSource,
+}
+
+/// 重复出现的参数，scrcpy 以最后一个为准
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct ArgConflict {
+    /// 规范的参数名，如 --max-size
+    pub flag: String,
+    /// 按出现顺序排列的全部参数
+    pub args: Vec<LaunchArg>,
+}
+
+/// 启动计划：实际运行的程序、参数和环境变量
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct LaunchPlan {
+    pub program: PathBuf,
+    /// 目标设备序列号
+    pub serial: String,
+    /// 设备所在的 ADB Server
+    pub server: AdbServer,
+    /// 参数及其来源 (按传给 scrcpy 的顺序)
+    pub args: Vec<LaunchArg>,
+    /// 额外设置的环境变量
+    pub env: IndexMap<String, String>,
+    pub conflicts: Vec<ArgConflict>,
+    /// 与当前 scrcpy 版本的兼容性
+    pub compat: CompatReport,
+}
+
+impl LaunchPlan {
+    /// 传给 scrcpy 的参数列表
+    pub fn argv(&self) -> Vec<String> {
+        self.args.iter().map(|a| a.arg.clone()).collect()
+    }
+}
+
+/// 找出重复出现的参数 (短参数按对应的长参数比较，如 -m 与 --max-size)
+fn find_conflicts(args: &[LaunchArg]) -> Vec<ArgConflict> {
+    let mut groups: IndexMap<String, Vec<LaunchArg>> = IndexMap::new();
+    for arg in args {
+        if let Some(flag) = scrcpy_args::flag_name(&arg.arg) {
+            groups.entry(flag).or_default().push(arg.clone());
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, args)| args.len() > 1)
+        .map(|(flag, args)| ArgConflict { flag, args })
+        .collect()
+}
+
+/// 确定目标设备并构建启动命令 (不启动)
+///
+/// 参数依次来自：设备、远程 ADB Server、scrcpy 选项 (应用专属参数 > 应用流转参数 > 全局参数)、启动模式。
+pub fn plan_launch(mode: &LaunchMode, serial: Option<&str>) -> Result<LaunchPlan, String> {
     let config = Config::load();
-    let scrcpy_path = get_scrcpy_path();
-    
-    // 构建参数列表
-    let mut args: Vec<String> = Vec::new();
-    
-    // 根据模式确定使用哪些 scrcpy 选项
-    let scrcpy_args_list = match &mode {
+    // scrcpy 通过 ADB Server 连接设备，直连的设备不在 ADB Server 中
+    if let Some(direct) = direct_device(&config, serial) {
+        return Err(format!(
+            "设备 {} 使用直连方式，scrcpy 需要通过 ADB Server 连接，请先将连接方式切换为 ADB Server",
+            direct
+        ));
+    }
+    let serial = resolve_device(serial)?;
+
+    // 设备在远程 ADB Server 上时需要连接远程主机上的转发端口
+    let server = adb_host::server_for(Some(&serial));
+    let mut args: Vec<LaunchArg> = Vec::new();
+    let mut push = |source: ArgSource, list: Vec<String>| {
+        args.extend(list.into_iter().map(|arg| LaunchArg { arg, source }));
+    };
+    push(ArgSource::Device, vec![format!("--serial={}", serial)]);
+    push(ArgSource::AdbServer, server.scrcpy_args());
+
+    // 根据模式确定使用哪些 scrcpy 选项 (自定义参数按 shell 规则拆分)
+    let (source, list) = match mode {
         LaunchMode::App { scrcpy_args: Some(custom_args), .. } => {
             // 应用专属自定义参数
-            custom_args.split_whitespace().map(String::from).collect()
+            let list = scrcpy_args::split(custom_args).map_err(|e| format!("应用自定义参数有误: {}", e))?;
+            (ArgSource::AppScrcpyArgs, list)
         }
         LaunchMode::App { .. } if config.use_app_stream_args => {
             if config.use_app_custom_args {
-                config.app_custom_args.split_whitespace().map(String::from).collect()
+                let list = scrcpy_args::split(&config.app_custom_args)
+                    .map_err(|e| format!("应用流转自定义参数有误: {}", e))?;
+                (ArgSource::AppCustomArgs, list)
             } else {
-                build_args_from_options(&config.app_stream_options)
+                (ArgSource::AppStreamOptions, build_args_from_options(&config.app_stream_options))
             }
         }
         _ => {
             if config.use_custom_args {
-                config.custom_args.split_whitespace().map(String::from).collect()
+                let list = scrcpy_args::split(&config.custom_args).map_err(|e| format!("自定义参数有误: {}", e))?;
+                (ArgSource::CustomArgs, list)
             } else {
-                build_args_from_options(&config.scrcpy_options)
+                (ArgSource::ScrcpyOptions, build_args_from_options(&config.scrcpy_options))
             }
         }
     };
-    
-    args.extend(scrcpy_args_list);
-    
+    push(source, list);
+
     // 根据模式添加特定参数
-    match &mode {
+    match mode {
         LaunchMode::Audio => {
-            args.push("--no-video".to_string());
+            push(ArgSource::Mode, vec!["--no-video".to_string()]);
         }
         LaunchMode::Mirror => {
             let res_str = &config.global_settings.full_res;
-            if let Some(max_dim) = res_str
+            let max_dim = res_str
                 .split('x')
                 .filter_map(|s| s.parse::<u32>().ok())
                 .max()
-            {
-                args.push(format!("--max-size={}", max_dim));
-            } else {
-                args.push("--max-size=1920".to_string());
-            }
+                .unwrap_or(1920);
+            push(ArgSource::GlobalSettings, vec![format!("--max-size={}", max_dim)]);
         }
         LaunchMode::App { package, settings, .. } => {
-            let (dpi, full_res) = if let Some(s) = settings {
-                (s.dpi, s.full_res.clone())
+            let (source, dpi, full_res) = if let Some(s) = settings {
+                (ArgSource::AppSettings, s.dpi, s.full_res.clone())
             } else {
-                (config.global_settings.dpi, config.global_settings.full_res.clone())
+                (ArgSource::GlobalSettings, config.global_settings.dpi, config.global_settings.full_res.clone())
             };
-            
-            args.push(format!("--new-display={}/{}", full_res, dpi));
-            args.push(format!("--start-app={}", package));
-            args.push("--capture-orientation=0".to_string());
+
+            push(source, vec![format!("--new-display={}/{}", full_res, dpi)]);
+            push(ArgSource::Mode, vec![
+                format!("--start-app={}", package),
+                "--capture-orientation=0".to_string(),
+            ]);
         }
     }
-    
+
+    // scrcpy 通过 ADB 环境变量使用与启动器相同的 adb
+    let mut env = IndexMap::new();
+    env.insert("ADB".to_string(), get_adb_path().to_string_lossy().into_owned());
+    // 与 create_command 相同，选中团队密钥时传给 scrcpy 启动的 adb
+    if let Some(keys) = adb_key::vendor_keys() {
+        env.insert("ADB_VENDOR_KEYS".to_string(), keys.to_string_lossy().into_owned());
+    }
+    if let Some((key, value)) = server.scrcpy_env() {
+        env.insert(key.to_string(), value);
+    }
+
+    let argv: Vec<String> = args.iter().map(|a| a.arg.clone()).collect();
+    Ok(LaunchPlan {
+        program: get_scrcpy_path(),
+        serial,
+        server,
+        conflicts: find_conflicts(&args),
+        compat: scrcpy_compat::report(&argv, binaries::scrcpy_version()),
+        args,
+        env,
+    })
+}
+
+/// 构建并运行 scrcpy 命令
+///
+/// `serial` 为 None 时使用当前设备，规则见 `resolve_device`，参数的构建见 `plan_launch`。
+/// 启动成功的进程登记到 `SessionManager`。
+pub fn launch_scrcpy<R: Runtime>(app: &AppHandle<R>, mode: LaunchMode, serial: Option<&str>) -> CommandResult {
+    // 1. 预检查：确定目标设备并构建参数
+    let plan = match plan_launch(&mode, serial) {
+        Ok(plan) => plan,
+        Err(message) => return CommandResult {
+            success: false,
+            message: format!("启动失败: {}", message),
+        },
+    };
+
+    // 旧版 scrcpy 不认识的参数会导致立即退出，启动前先给出明确的提示
+    if !plan.compat.warnings.is_empty() {
+        return CommandResult {
+            success: false,
+            message: format!("启动失败: {}", scrcpy_compat::describe(&plan.compat)),
+        };
+    }
+    let serial = &plan.serial;
+    let args = plan.argv();
+
     // 启动 scrcpy (捕获 stderr 以便获取错误)
-    let mut child = match create_command(&scrcpy_path)
+    let mut command = create_command(&plan.program);
+    command.envs(&plan.env);
+    let mut child = match command
         .args(&args)
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
@@ -789,11 +813,9 @@ pub fn launch_scrcpy(mode: LaunchMode) -> CommandResult {
             }
         }
         Ok(None) => {
-            // 进程仍在运行，视为启动成功
-            // 释放子进程句柄，使其在脱离后继续运行 (Windows需要 drop child)
-            // 在 Rust 中，Process 默认如果不 wait，drop 时不会 kill，但为了保险起见，或者如果这里的 child 是 Child 类型，drop 是安全的。
-            // 不过如果是 `Command::spawn` 返回的 Child，drop 不会 kill。
-            
+            // 进程仍在运行，视为启动成功，交给会话管理器等待其退出
+            app.state::<SessionManager>().register(app, child, serial, mode.name(), mode.package(), &args);
+
             let mode_str = match &mode {
                 LaunchMode::Mirror => "屏幕镜像",
                 LaunchMode::Audio => "纯音频",
@@ -801,7 +823,7 @@ pub fn launch_scrcpy(mode: LaunchMode) -> CommandResult {
             };
             CommandResult {
                 success: true,
-                message: format!("已启动 ({})", mode_str),
+                message: format!("已启动 ({}): {}", mode_str, serial),
             }
         }
         Err(e) => {
@@ -839,4 +861,40 @@ mod tests {
         assert!(args.contains(&"--stay-awake".to_string()));
         assert!(args.contains(&"--max-fps=60".to_string()));
     }
+
+    #[test]
+    fn test_find_conflicts() {
+        let arg = |arg: &str, source| LaunchArg { arg: arg.to_string(), source };
+        let args = vec![
+            arg("--serial=R58M123", ArgSource::Device),
+            arg("-K", ArgSource::CustomArgs),
+            arg("-m", ArgSource::CustomArgs),
+            arg("1024", ArgSource::CustomArgs),
+            arg("--keyboard=sdk", ArgSource::CustomArgs),
+            arg("--max-size=2670", ArgSource::GlobalSettings),
+        ];
+        let conflicts = find_conflicts(&args);
+        let flags: Vec<&str> = conflicts.iter().map(|c| c.flag.as_str()).collect();
+        assert_eq!(flags, ["--keyboard", "--max-size"]);
+        assert_eq!(conflicts[1].args[1].source, ArgSource::GlobalSettings);
+    }
+
+    #[test]
+    fn test_pick_device() {
+        let devices = crate::adb_host::parse_devices("\
+emulator-5554 device product:sdk model:Pixel_7 transport_id:1
+R58M123 device product:a52 model:SM_A525F transport_id:2
+192.168.1.5:5555 unauthorized transport_id:3
+");
+        // 多台设备且未选择时报错
+        assert!(pick_device(None, None, &devices).unwrap_err().contains("多台设备"));
+        // 当前设备 / 显式指定
+        assert_eq!(pick_device(None, Some("R58M123"), &devices).unwrap(), "R58M123");
+        assert_eq!(pick_device(Some("emulator-5554"), Some("R58M123"), &devices).unwrap(), "emulator-5554");
+        assert!(pick_device(Some("192.168.1.5:5555"), None, &devices).is_err());
+        assert!(pick_device(Some("missing"), None, &devices).is_err());
+        // 当前设备已断开，只剩一台时自动选择
+        assert_eq!(pick_device(None, Some("gone"), &devices[..1]).unwrap(), "emulator-5554");
+        assert!(pick_device(None, None, &devices[2..]).unwrap_err().contains("未授权"));
+    }
 }
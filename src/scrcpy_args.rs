@@ -0,0 +1,683 @@
+//! Scrcpy Launcher - scrcpy 参数模块
+//!
+//! `ScrcpyOptions` 中各选项的取值类型，以及从选项生成 scrcpy 命令行参数。
+//! 每个参数都生成为单独的 `--name=value` 形式，避免 `-m 1024` 这类
+//! 把参数名和值放在同一个 argv 元素中的问题。
+//!
+//! 自定义参数按 shell 规则拆分 (`split`)，也可以导入为结构化选项 (`from_args`)，
+//! 无法识别的参数保留在 `extra_args` 中，两种模式之间切换不会丢失参数。
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use crate::config::ScrcpyOptions;
+
+/// 命令行取值 (枚举选项与 scrcpy 参数值之间的转换)
+pub trait CliValue: Copy + Sized + 'static {
+    /// 全部取值
+    const ALL: &'static [Self];
+
+    /// scrcpy 参数值
+    fn as_str(self) -> &'static str;
+
+    /// 从 scrcpy 参数值解析
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|v| v.as_str() == value)
+    }
+}
+
+/// 视频编码 (`--video-codec`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoCodec {
+    H264,
+    H265,
+    Av1,
+}
+
+impl CliValue for VideoCodec {
+    const ALL: &'static [Self] = &[Self::H264, Self::H265, Self::Av1];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::H264 => "h264",
+            Self::H265 => "h265",
+            Self::Av1 => "av1",
+        }
+    }
+}
+
+/// 音频编码 (`--audio-codec`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioCodec {
+    Opus,
+    Aac,
+    Flac,
+    Raw,
+}
+
+impl CliValue for AudioCodec {
+    const ALL: &'static [Self] = &[Self::Opus, Self::Aac, Self::Flac, Self::Raw];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Opus => "opus",
+            Self::Aac => "aac",
+            Self::Flac => "flac",
+            Self::Raw => "raw",
+        }
+    }
+}
+
+/// 音频来源 (`--audio-source`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AudioSource {
+    /// 设备音频输出 (默认)
+    Output,
+    /// 应用播放的音频 (不影响设备本身的播放)
+    Playback,
+    Mic,
+    MicUnprocessed,
+    MicCamcorder,
+    MicVoiceRecognition,
+    MicVoiceCommunication,
+    VoiceCall,
+    VoiceCallUplink,
+    VoiceCallDownlink,
+    VoicePerformance,
+}
+
+impl CliValue for AudioSource {
+    const ALL: &'static [Self] = &[
+        Self::Output,
+        Self::Playback,
+        Self::Mic,
+        Self::MicUnprocessed,
+        Self::MicCamcorder,
+        Self::MicVoiceRecognition,
+        Self::MicVoiceCommunication,
+        Self::VoiceCall,
+        Self::VoiceCallUplink,
+        Self::VoiceCallDownlink,
+        Self::VoicePerformance,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Output => "output",
+            Self::Playback => "playback",
+            Self::Mic => "mic",
+            Self::MicUnprocessed => "mic-unprocessed",
+            Self::MicCamcorder => "mic-camcorder",
+            Self::MicVoiceRecognition => "mic-voice-recognition",
+            Self::MicVoiceCommunication => "mic-voice-communication",
+            Self::VoiceCall => "voice-call",
+            Self::VoiceCallUplink => "voice-call-uplink",
+            Self::VoiceCallDownlink => "voice-call-downlink",
+            Self::VoicePerformance => "voice-performance",
+        }
+    }
+}
+
+/// 画面方向 (`--orientation`)，flip 表示先水平翻转
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    #[serde(rename = "0")]
+    Deg0,
+    #[serde(rename = "90")]
+    Deg90,
+    #[serde(rename = "180")]
+    Deg180,
+    #[serde(rename = "270")]
+    Deg270,
+    #[serde(rename = "flip0")]
+    Flip0,
+    #[serde(rename = "flip90")]
+    Flip90,
+    #[serde(rename = "flip180")]
+    Flip180,
+    #[serde(rename = "flip270")]
+    Flip270,
+}
+
+impl CliValue for Orientation {
+    const ALL: &'static [Self] = &[
+        Self::Deg0,
+        Self::Deg90,
+        Self::Deg180,
+        Self::Deg270,
+        Self::Flip0,
+        Self::Flip90,
+        Self::Flip180,
+        Self::Flip270,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Deg0 => "0",
+            Self::Deg90 => "90",
+            Self::Deg180 => "180",
+            Self::Deg270 => "270",
+            Self::Flip0 => "flip0",
+            Self::Flip90 => "flip90",
+            Self::Flip180 => "flip180",
+            Self::Flip270 => "flip270",
+        }
+    }
+}
+
+/// 键盘/鼠标输入方式 (`--keyboard` / `--mouse`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputMode {
+    Disabled,
+    /// Android 输入事件注入 (默认)
+    Sdk,
+    /// 模拟 HID 设备
+    Uhid,
+    /// 通过 USB AOA 模拟 HID 设备 (仅 USB 连接)
+    Aoa,
+}
+
+impl CliValue for InputMode {
+    const ALL: &'static [Self] = &[Self::Disabled, Self::Sdk, Self::Uhid, Self::Aoa];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Disabled => "disabled",
+            Self::Sdk => "sdk",
+            Self::Uhid => "uhid",
+            Self::Aoa => "aoa",
+        }
+    }
+}
+
+/// 快捷键修饰键 (`--shortcut-mod`)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutKey {
+    Lctrl,
+    Rctrl,
+    Lalt,
+    Ralt,
+    Lsuper,
+    Rsuper,
+}
+
+impl CliValue for ShortcutKey {
+    const ALL: &'static [Self] = &[
+        Self::Lctrl,
+        Self::Rctrl,
+        Self::Lalt,
+        Self::Ralt,
+        Self::Lsuper,
+        Self::Rsuper,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lctrl => "lctrl",
+            Self::Rctrl => "rctrl",
+            Self::Lalt => "lalt",
+            Self::Ralt => "ralt",
+            Self::Lsuper => "lsuper",
+            Self::Rsuper => "rsuper",
+        }
+    }
+}
+
+/// 裁剪区域 (`--crop=宽:高:X:Y`，设备原始分辨率下的像素)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Crop {
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl std::fmt::Display for Crop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}:{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// 非空字符串
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// 大于 0 的数值 (0 表示使用 scrcpy 默认值)
+fn positive(value: u32) -> Option<u32> {
+    (value > 0).then_some(value)
+}
+
+/// 开关参数
+fn flag(args: &mut Vec<String>, name: &str, enabled: bool) {
+    if enabled {
+        args.push(format!("--{}", name));
+    }
+}
+
+/// 带值参数，值为空时不生成
+fn value(args: &mut Vec<String>, name: &str, value: Option<impl std::fmt::Display>) {
+    if let Some(value) = value {
+        args.push(format!("--{}={}", name, value));
+    }
+}
+
+/// 从选项生成 scrcpy 参数，未设置 (None / 0 / 空) 的选项不生成参数
+pub fn to_args(opts: &ScrcpyOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    let a = &mut args;
+
+    // 输入：指定了键盘输入方式时忽略旧的 hid_keyboard 开关 (-K 即 --keyboard=uhid)
+    match opts.keyboard {
+        Some(mode) => value(a, "keyboard", Some(mode.as_str())),
+        None if opts.hid_keyboard => a.push("-K".to_string()),
+        None => {}
+    }
+    value(a, "mouse", opts.mouse.map(|m| m.as_str()));
+    if !opts.shortcut_mod.is_empty() {
+        let keys: Vec<&str> = opts.shortcut_mod.iter().map(|k| k.as_str()).collect();
+        value(a, "shortcut-mod", Some(keys.join(",")));
+    }
+
+    // 视频
+    value(a, "video-codec", opts.video_codec.map(|c| c.as_str()));
+    value(a, "video-encoder", non_empty(&opts.video_encoder));
+    value(a, "video-bit-rate", positive(opts.video_bit_rate));
+    value(a, "max-size", positive(opts.max_size));
+    value(a, "max-fps", positive(opts.max_fps));
+    value(a, "crop", opts.crop);
+    value(a, "orientation", opts.orientation.map(|o| o.as_str()));
+    value(a, "display-id", opts.display_id);
+
+    // 音频
+    value(a, "audio-codec", opts.audio_codec.map(|c| c.as_str()));
+    value(a, "audio-source", opts.audio_source.map(|s| s.as_str()));
+    value(a, "audio-bit-rate", positive(opts.audio_bit_rate));
+    value(a, "audio-buffer", positive(opts.audio_buffer));
+
+    // 设备
+    flag(a, "stay-awake", opts.stay_awake);
+    flag(a, "turn-screen-off", opts.turn_screen_off);
+    flag(a, "show-touches", opts.show_touches);
+    flag(a, "power-off-on-close", opts.power_off_on_close);
+    value(a, "screen-off-timeout", positive(opts.screen_off_timeout));
+    value(a, "display-ime-policy", opts.local_ime.then_some("local"));
+
+    // 窗口
+    value(a, "window-title", non_empty(&opts.window_title));
+    value(a, "window-x", opts.window_x);
+    value(a, "window-y", opts.window_y);
+    value(a, "window-width", positive(opts.window_width));
+    value(a, "window-height", positive(opts.window_height));
+    flag(a, "always-on-top", opts.always_on_top);
+    flag(a, "window-borderless", opts.borderless);
+
+    // 无法映射的参数原样追加
+    args.extend(opts.extra_args.iter().cloned());
+    args
+}
+
+/// 按 shell 规则拆分参数
+///
+/// - 空白分隔参数，相邻的引号部分与普通字符拼接为同一个参数 (`--window-title="My App"`)
+/// - 单引号内原样保留
+/// - 双引号内 `\"` 和 `\\` 为转义，其它反斜杠原样保留
+/// - 引号外反斜杠只转义空白、引号和反斜杠，Windows 路径 `C:\scrcpy` 不受影响
+pub fn split(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    // 当前参数已开始 (""、'' 也算一个空参数)
+    let mut in_word = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => bail!("单引号未闭合"),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.peek(), Some('"' | '\\')) => {
+                            current.push(chars.next().unwrap());
+                        }
+                        Some(c) => current.push(c),
+                        None => bail!("双引号未闭合"),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                match chars.peek() {
+                    Some(&next) if next.is_whitespace() || matches!(next, '"' | '\'' | '\\') => {
+                        current.push(next);
+                        chars.next();
+                    }
+                    _ => current.push('\\'),
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// 按 `split` 的规则给参数加引号 (`split(&quote(s))` 得到原参数)
+pub fn quote(arg: &str) -> String {
+    let needs_quote = arg.is_empty()
+        || arg.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'));
+    if !needs_quote {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut chars = arg.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            // 反斜杠只在后面是引号、反斜杠或结尾时需要转义
+            '\\' if matches!(chars.peek(), None | Some('"' | '\\')) => quoted.push_str("\\\\"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// 将参数列表拼接为命令行文本
+pub fn join(args: &[String]) -> String {
+    args.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" ")
+}
+
+/// 命令行导入结果
+#[derive(Serialize, Clone, Debug)]
+pub struct ArgsImport {
+    /// 导入的选项，无法映射的参数在 `extra_args` 中
+    pub options: ScrcpyOptions,
+    /// 无法映射的参数 (每项为一个参数及其值的命令行文本)
+    pub unmapped: Vec<String>,
+}
+
+/// 带值的参数 (其余视为开关)
+const VALUE_OPTIONS: &[&str] = &[
+    "keyboard", "mouse", "shortcut-mod",
+    "video-codec", "video-encoder", "video-bit-rate", "max-size", "max-fps",
+    "crop", "orientation", "display-id",
+    "audio-codec", "audio-source", "audio-bit-rate", "audio-buffer",
+    "screen-off-timeout", "display-ime-policy",
+    "window-title", "window-x", "window-y", "window-width", "window-height",
+];
+
+/// 短参数对应的长参数名 (`-K`、`-M` 的值在 `apply_flag` 中处理)
+fn long_name(short: char) -> Option<&'static str> {
+    Some(match short {
+        'm' => "max-size",
+        'b' => "video-bit-rate",
+        'S' => "turn-screen-off",
+        'w' => "stay-awake",
+        't' => "show-touches",
+        'K' => "hid-keyboard",
+        'M' => "hid-mouse",
+        _ => return None,
+    })
+}
+
+/// 参数的规范名称，短参数转为对应的长参数 (`-m1024` -> `--max-size`，`-K` -> `--keyboard`)
+///
+/// 不是参数 (如单独的值) 时返回 None。
+pub fn flag_name(arg: &str) -> Option<String> {
+    if let Some(long) = arg.strip_prefix("--") {
+        return Some(format!("--{}", long.split('=').next().unwrap_or(long)));
+    }
+    let short = arg.strip_prefix('-')?.chars().next().filter(|c| !c.is_ascii_digit())?;
+    Some(match long_name(short) {
+        Some("hid-keyboard") => "--keyboard".to_string(),
+        Some("hid-mouse") => "--mouse".to_string(),
+        Some(name) => format!("--{}", name),
+        None => format!("-{}", short),
+    })
+}
+
+/// 码率，支持 K / M 后缀 (如 8M)
+fn parse_bit_rate(value: &str) -> Option<u32> {
+    let (number, multiplier) = match value.char_indices().last()? {
+        (i, 'k' | 'K') => (&value[..i], 1_000),
+        (i, 'm' | 'M') => (&value[..i], 1_000_000),
+        _ => (value, 1),
+    };
+    number.parse::<u32>().ok()?.checked_mul(multiplier)
+}
+
+fn parse_crop(value: &str) -> Option<Crop> {
+    let parts: Vec<u32> = value.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    match parts[..] {
+        [width, height, x, y] => Some(Crop { width, height, x, y }),
+        _ => None,
+    }
+}
+
+/// 设置开关选项，无法映射时返回 false
+fn apply_flag(opts: &mut ScrcpyOptions, name: &str) -> bool {
+    match name {
+        "hid-keyboard" => opts.hid_keyboard = true,
+        "hid-mouse" => opts.mouse = Some(InputMode::Uhid),
+        "stay-awake" => opts.stay_awake = true,
+        "turn-screen-off" => opts.turn_screen_off = true,
+        "show-touches" => opts.show_touches = true,
+        "power-off-on-close" => opts.power_off_on_close = true,
+        "always-on-top" => opts.always_on_top = true,
+        "window-borderless" => opts.borderless = true,
+        _ => return false,
+    }
+    true
+}
+
+/// 设置带值选项，无法映射或值无效时返回 None
+fn apply_value(opts: &mut ScrcpyOptions, name: &str, value: &str) -> Option<()> {
+    let positive = || value.parse::<u32>().ok().filter(|v| *v > 0);
+    match name {
+        "keyboard" => opts.keyboard = Some(InputMode::parse(value)?),
+        "mouse" => opts.mouse = Some(InputMode::parse(value)?),
+        "shortcut-mod" => {
+            opts.shortcut_mod = value.split(',').map(ShortcutKey::parse).collect::<Option<_>>()?;
+        }
+        "video-codec" => opts.video_codec = Some(VideoCodec::parse(value)?),
+        "video-encoder" => opts.video_encoder = Some(value.to_string()),
+        "video-bit-rate" => opts.video_bit_rate = parse_bit_rate(value)?,
+        "max-size" => opts.max_size = positive()?,
+        "max-fps" => opts.max_fps = positive()?,
+        "crop" => opts.crop = Some(parse_crop(value)?),
+        "orientation" => opts.orientation = Some(Orientation::parse(value)?),
+        "display-id" => opts.display_id = Some(value.parse().ok()?),
+        "audio-codec" => opts.audio_codec = Some(AudioCodec::parse(value)?),
+        "audio-source" => opts.audio_source = Some(AudioSource::parse(value)?),
+        "audio-bit-rate" => opts.audio_bit_rate = parse_bit_rate(value)?,
+        "audio-buffer" => opts.audio_buffer = positive()?,
+        "screen-off-timeout" => opts.screen_off_timeout = positive()?,
+        "display-ime-policy" if value == "local" => opts.local_ime = true,
+        "window-title" => opts.window_title = Some(value.to_string()),
+        "window-x" => opts.window_x = Some(value.parse().ok()?),
+        "window-y" => opts.window_y = Some(value.parse().ok()?),
+        "window-width" => opts.window_width = positive()?,
+        "window-height" => opts.window_height = positive()?,
+        _ => return None,
+    }
+    Some(())
+}
+
+/// 是否为 scrcpy 程序本身 (导入完整命令行时跳过)
+fn is_program(arg: &str) -> bool {
+    let name = arg.rsplit(['/', '\\']).next().unwrap_or(arg).to_ascii_lowercase();
+    name == "scrcpy" || name == "scrcpy.exe"
+}
+
+/// 将 scrcpy 参数导入为结构化选项
+///
+/// 从全部关闭的选项开始 (命令行中没有的开关即为关闭)，
+/// 无法映射的参数 (如 `--record`、`--serial`) 放入 `extra_args` 并在 `unmapped` 中列出。
+pub fn from_args(args: &[String]) -> ArgsImport {
+    let mut options = ScrcpyOptions {
+        hid_keyboard: false,
+        stay_awake: false,
+        ..ScrcpyOptions::default()
+    };
+    let mut unmapped = Vec::new();
+    let mut args = args.iter().peekable();
+    if args.peek().is_some_and(|a| is_program(a)) {
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        // 统一为 (长参数名, 内联值)
+        let (name, inline) = if let Some(long) = arg.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((name, value)) => (Some(name.to_string()), Some(value.to_string())),
+                None => (Some(long.to_string()), None),
+            }
+        } else if let Some(short) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+            // 短参数的值可以紧跟在后面 (-m1024)
+            let mut chars = short.chars();
+            let name = chars.next().and_then(long_name).map(str::to_string);
+            let rest = chars.as_str();
+            (name, (!rest.is_empty()).then(|| rest.trim_start_matches('=').to_string()))
+        } else {
+            (None, None)
+        };
+
+        let mapped = match name.as_deref() {
+            Some(name) if VALUE_OPTIONS.contains(&name) => {
+                let value = inline.clone().or_else(|| args.next().cloned());
+                let ok = value.as_deref().is_some_and(|v| apply_value(&mut options, name, v).is_some());
+                if !ok {
+                    let mut raw = vec![arg.clone()];
+                    raw.extend(value.filter(|_| inline.is_none()));
+                    unmapped.push(join(&raw));
+                    options.extra_args.extend(raw);
+                }
+                continue;
+            }
+            Some(name) if inline.is_none() => apply_flag(&mut options, name),
+            _ => false,
+        };
+        if mapped {
+            continue;
+        }
+
+        // 未知参数：scrcpy 没有位置参数，后面紧跟的非 - 开头的参数视为它的值
+        let mut raw = vec![arg.clone()];
+        if arg.starts_with('-') && !arg.contains('=') {
+            if let Some(value) = args.next_if(|next| !next.starts_with('-')) {
+                raw.push(value.clone());
+            }
+        }
+        unmapped.push(join(&raw));
+        options.extra_args.extend(raw);
+    }
+
+    ArgsImport { options, unmapped }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_args() {
+        let mut opts = ScrcpyOptions {
+            hid_keyboard: false,
+            stay_awake: false,
+            ..ScrcpyOptions::default()
+        };
+        assert!(to_args(&opts).is_empty());
+
+        opts.hid_keyboard = true;
+        opts.max_size = 1024;
+        opts.video_codec = Some(VideoCodec::H265);
+        opts.video_bit_rate = 8_000_000;
+        opts.audio_source = Some(AudioSource::MicVoiceRecognition);
+        opts.crop = Some(Crop { width: 1224, height: 1440, x: 0, y: 0 });
+        opts.orientation = Some(Orientation::Flip90);
+        opts.display_id = Some(0);
+        opts.shortcut_mod = vec![ShortcutKey::Lctrl, ShortcutKey::Rsuper];
+        opts.window_title = Some("My Phone".to_string());
+        opts.window_x = Some(-10);
+        opts.screen_off_timeout = 300;
+        let args = to_args(&opts);
+        for expected in [
+            "-K",
+            "--max-size=1024",
+            "--video-codec=h265",
+            "--video-bit-rate=8000000",
+            "--audio-source=mic-voice-recognition",
+            "--crop=1224:1440:0:0",
+            "--orientation=flip90",
+            "--display-id=0",
+            "--shortcut-mod=lctrl,rsuper",
+            "--window-title=My Phone",
+            "--window-x=-10",
+            "--screen-off-timeout=300",
+        ] {
+            assert!(args.iter().any(|a| a == expected), "缺少 {}: {:?}", expected, args);
+        }
+
+        // 指定的输入方式优先于 hid_keyboard
+        opts.keyboard = Some(InputMode::Disabled);
+        assert!(to_args(&opts).contains(&"--keyboard=disabled".to_string()));
+        assert_eq!(Orientation::parse("flip90"), Some(Orientation::Flip90));
+    }
+
+    #[test]
+    fn test_split() {
+        let args = split(r#"-K  --window-title="My App" --record 'a b.mp4' C:\scrcpy\x.mp4 a\ b "" "q\"x""#).unwrap();
+        assert_eq!(args, [
+            "-K", "--window-title=My App", "--record", "a b.mp4", r"C:\scrcpy\x.mp4", "a b", "", "q\"x",
+        ]);
+        assert!(split("--window-title=\"x").is_err());
+        assert!(split("   ").unwrap().is_empty());
+
+        for arg in ["My App", "", r"C:\Program Files\", "say \"hi\"", "it's"] {
+            assert_eq!(split(&quote(arg)).unwrap(), [arg]);
+        }
+    }
+
+    #[test]
+    fn test_from_args() {
+        let args = split(r#"scrcpy.exe -K -m1024 -b 8M --window-title="My App" --shortcut-mod=lctrl --record file.mp4 --no-audio --video-codec=vp9 -S"#).unwrap();
+        let import = from_args(&args);
+        let opts = &import.options;
+        assert!(opts.hid_keyboard && opts.turn_screen_off && !opts.stay_awake);
+        assert_eq!(opts.max_size, 1024);
+        assert_eq!(opts.video_bit_rate, 8_000_000);
+        assert_eq!(opts.window_title.as_deref(), Some("My App"));
+        assert_eq!(opts.shortcut_mod, [ShortcutKey::Lctrl]);
+        assert_eq!(import.unmapped, ["--record file.mp4", "--no-audio", "--video-codec=vp9"]);
+        assert_eq!(opts.extra_args, ["--record", "file.mp4", "--no-audio", "--video-codec=vp9"]);
+
+        // 导出后再导入得到相同的参数
+        let exported = to_args(opts);
+        assert_eq!(to_args(&from_args(&exported).options), exported);
+    }
+}
@@ -0,0 +1,168 @@
+//! Scrcpy Launcher - scrcpy 版本兼容性检查
+//!
+//! 新版 scrcpy 才有的参数 (如 `--new-display`、`--start-app`、`--display-ime-policy`)
+//! 交给旧版本时，scrcpy 会立即报"unrecognized option"退出。
+//! 启动前按兼容性表检查参数，给出不支持的参数及所需的最低版本。
+
+use std::fmt;
+use serde::Serialize;
+
+/// scrcpy 版本号
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// 解析 `2.4`、`3.1.1`、`v3.0-dirty` 等版本号
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        let end = version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(version.len());
+        let mut parts = version[..end].split('.').map(|p| p.parse::<u32>());
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.patch {
+            0 => write!(f, "{}.{}", self.major, self.minor),
+            patch => write!(f, "{}.{}.{}", self.major, self.minor, patch),
+        }
+    }
+}
+
+/// 兼容性表：参数及首次支持它的 scrcpy 版本 (早于 2.0 的参数不在表中)
+const COMPAT_TABLE: &[(&str, Version)] = &[
+    ("--video-codec", Version::new(2, 0, 0)),
+    ("--video-encoder", Version::new(2, 0, 0)),
+    ("--video-bit-rate", Version::new(2, 0, 0)),
+    ("--audio-codec", Version::new(2, 0, 0)),
+    ("--audio-encoder", Version::new(2, 0, 0)),
+    ("--audio-bit-rate", Version::new(2, 0, 0)),
+    ("--audio-buffer", Version::new(2, 0, 0)),
+    ("--no-audio", Version::new(2, 0, 0)),
+    ("--no-video", Version::new(2, 1, 0)),
+    ("--audio-source", Version::new(2, 1, 0)),
+    ("--display-id", Version::new(2, 2, 0)),
+    ("--video-source", Version::new(2, 2, 0)),
+    ("--orientation", Version::new(2, 3, 0)),
+    ("--display-orientation", Version::new(2, 3, 0)),
+    ("--record-orientation", Version::new(2, 3, 0)),
+    ("--keyboard", Version::new(2, 4, 0)),
+    ("--mouse", Version::new(2, 4, 0)),
+    ("--screen-off-timeout", Version::new(2, 5, 0)),
+    ("--new-display", Version::new(3, 0, 0)),
+    ("--start-app", Version::new(3, 0, 0)),
+    ("--display-ime-policy", Version::new(3, 0, 0)),
+    ("--capture-orientation", Version::new(3, 0, 0)),
+];
+
+/// 一个当前版本不支持的参数
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CompatWarning {
+    /// 参数名，如 --new-display
+    pub flag: String,
+    /// 完整参数，如 --new-display=1920x1080/320
+    pub arg: String,
+    /// 需要的最低版本
+    pub min_version: String,
+    /// 当前版本
+    pub version: String,
+}
+
+impl fmt::Display for CompatWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 需要 scrcpy {} 或更高版本", self.flag, self.min_version)
+    }
+}
+
+/// 检查结果
+#[derive(Serialize, Clone, Debug)]
+pub struct CompatReport {
+    /// 当前 scrcpy 版本，无法获取时为空 (此时不检查)
+    pub version: Option<String>,
+    pub warnings: Vec<CompatWarning>,
+}
+
+/// 参数首次支持的版本，不在表中时返回 None
+pub fn min_version(flag: &str) -> Option<Version> {
+    COMPAT_TABLE
+        .iter()
+        .find(|(name, _)| *name == flag)
+        .map(|(_, version)| *version)
+}
+
+/// 检查参数列表，返回 `version` 不支持的参数
+pub fn check(args: &[String], version: Version) -> Vec<CompatWarning> {
+    args.iter()
+        .filter_map(|arg| {
+            let flag = arg.split('=').next().unwrap_or(arg);
+            let min = min_version(flag)?;
+            (version < min).then(|| CompatWarning {
+                flag: flag.to_string(),
+                arg: arg.clone(),
+                min_version: min.to_string(),
+                version: version.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 按 `scrcpy --version` 报告的版本检查，版本未知时不检查
+pub fn report(args: &[String], version: Option<String>) -> CompatReport {
+    let warnings = version
+        .as_deref()
+        .and_then(Version::parse)
+        .map(|v| check(args, v))
+        .unwrap_or_default();
+    CompatReport { version, warnings }
+}
+
+/// 用于错误提示的文字
+pub fn describe(report: &CompatReport) -> String {
+    let flags: Vec<String> = report.warnings.iter().map(|w| w.to_string()).collect();
+    format!(
+        "当前 scrcpy {} 不支持以下参数，请升级 scrcpy 或修改启动参数：{}",
+        report.version.as_deref().unwrap_or("未知版本"),
+        flags.join("；")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(Version::parse("2.4"), Some(Version::new(2, 4, 0)));
+        assert_eq!(Version::parse("v3.1.1-dirty"), Some(Version::new(3, 1, 1)));
+        assert_eq!(Version::parse("unknown"), None);
+        assert!(Version::new(2, 10, 0) > Version::new(2, 4, 0));
+
+        let args: Vec<String> = ["--serial=abc", "--keyboard=uhid", "--new-display=1920x1080/320", "--start-app=com.example"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let warnings = check(&args, Version::new(2, 4, 0));
+        let flags: Vec<&str> = warnings.iter().map(|w| w.flag.as_str()).collect();
+        assert_eq!(flags, ["--new-display", "--start-app"]);
+        assert_eq!(warnings[0].min_version, "3.0");
+        assert_eq!(warnings[0].arg, "--new-display=1920x1080/320");
+
+        assert!(check(&args, Version::new(3, 1, 0)).is_empty());
+        assert!(report(&args, None).warnings.is_empty());
+        assert_eq!(report(&args, Some("2.1".into())).warnings.len(), 3);
+    }
+}
@@ -0,0 +1,287 @@
+//! Scrcpy Launcher - scrcpy 会话管理模块
+//!
+//! 记录启动器启动的每个 scrcpy 进程 (PID、设备、模式、包名、启动时间、参数)，
+//! 只停止自己启动的进程，不影响用户通过脚本等方式另外启动的 scrcpy。
+//! 每个会话有一个后台线程等待进程退出，退出时推送 `session-ended` 事件。
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, ChildStderr};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use anyhow::{Result, Context};
+use indexmap::IndexMap;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// 检查进程是否退出的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// 保留的 stderr 行数 (异常退出时作为原因)
+const STDERR_LINES: usize = 10;
+
+/// 进程退出后等待 stderr 读完的最长时间
+const STDERR_WAIT: Duration = Duration::from_secs(2);
+
+/// 会话信息
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionInfo {
+    pub id: u64,
+    pub pid: u32,
+    pub serial: String,
+    /// mirror / audio / app
+    pub mode: String,
+    /// 应用流转的包名
+    pub package: Option<String>,
+    /// 启动时间 (Unix 毫秒)
+    pub started_at: u64,
+    pub args: Vec<String>,
+}
+
+/// `session-ended` 事件载荷
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionEnded {
+    #[serde(flatten)]
+    pub session: SessionInfo,
+    /// 退出码，被信号终止时为空
+    pub exit_code: Option<i32>,
+    /// 是否由启动器停止
+    pub stopped: bool,
+    /// 最后几行 stderr
+    pub stderr: String,
+}
+
+struct Session {
+    info: SessionInfo,
+    child: Arc<Mutex<Child>>,
+    stopped: Arc<AtomicBool>,
+}
+
+/// 会话管理器 (Tauri state)
+pub struct SessionManager {
+    sessions: Arc<Mutex<IndexMap<u64, Session>>>,
+    next_id: AtomicU64,
+}
+
+impl Default for SessionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 读取 stderr 的线程及保留的最后几行
+struct StderrTail {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    reader: JoinHandle<()>,
+}
+
+impl StderrTail {
+    /// 等读取线程读到结束后返回最后几行
+    ///
+    /// scrcpy 启动的子进程 (如 adb) 可能继承 stderr，管道迟迟不关闭，最多等待 `STDERR_WAIT`。
+    fn finish(self) -> String {
+        let deadline = Instant::now() + STDERR_WAIT;
+        while !self.reader.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        if self.reader.is_finished() {
+            let _ = self.reader.join();
+        }
+        let lines = self.lines.lock().unwrap();
+        lines.iter().cloned().collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// 持续读取 stderr，避免管道写满阻塞 scrcpy，同时保留最后几行
+fn drain_stderr(stderr: ChildStderr) -> StderrTail {
+    let lines = Arc::new(Mutex::new(VecDeque::new()));
+    let tail = lines.clone();
+    let reader = std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+            let mut tail = tail.lock().unwrap();
+            if tail.len() == STDERR_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+    });
+    StderrTail { lines, reader }
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(IndexMap::new())),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 登记一个已启动的 scrcpy 进程，并在后台等待其退出
+    pub fn register<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        child: Child,
+        serial: &str,
+        mode: &str,
+        package: Option<&str>,
+        args: &[String],
+    ) -> SessionInfo {
+        let app = app.clone();
+        self.register_with(child, serial, mode, package, args, move |ended| {
+            let _ = app.emit("session-ended", ended);
+        })
+    }
+
+    /// 登记进程，退出并移出会话列表后调用 `on_ended` (由 `register` 推送事件)
+    fn register_with(
+        &self,
+        mut child: Child,
+        serial: &str,
+        mode: &str,
+        package: Option<&str>,
+        args: &[String],
+        on_ended: impl FnOnce(SessionEnded) + Send + 'static,
+    ) -> SessionInfo {
+        let info = SessionInfo {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            pid: child.id(),
+            serial: serial.to_string(),
+            mode: mode.to_string(),
+            package: package.map(str::to_string),
+            started_at: now_millis(),
+            args: args.to_vec(),
+        };
+        let stderr = child.stderr.take().map(drain_stderr);
+        let child = Arc::new(Mutex::new(child));
+        let stopped = Arc::new(AtomicBool::new(false));
+        self.sessions.lock().unwrap().insert(info.id, Session {
+            info: info.clone(),
+            child: child.clone(),
+            stopped: stopped.clone(),
+        });
+
+        let sessions = self.sessions.clone();
+        let id = info.id;
+        std::thread::spawn(move || {
+            // 轮询而不是 wait()，以便 stop() 随时可以拿到进程句柄
+            let status = loop {
+                match child.lock().unwrap().try_wait() {
+                    Ok(Some(status)) => break Some(status),
+                    Ok(None) => {}
+                    Err(_) => break None,
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            };
+
+            let Some(session) = sessions.lock().unwrap().shift_remove(&id) else {
+                return;
+            };
+            // 等 stderr 读完，最后几行通常是退出原因
+            let stderr = stderr.map(StderrTail::finish).unwrap_or_default();
+            on_ended(SessionEnded {
+                session: session.info,
+                exit_code: status.and_then(|s| s.code()),
+                stopped: stopped.load(Ordering::Relaxed),
+                stderr,
+            });
+        });
+        info
+    }
+
+    /// 正在运行的会话 (按启动顺序)
+    pub fn list(&self) -> Vec<SessionInfo> {
+        self.sessions.lock().unwrap().values().map(|s| s.info.clone()).collect()
+    }
+
+    /// 停止一个会话，进程退出后由等待线程推送 `session-ended`
+    pub fn stop(&self, id: u64) -> Result<()> {
+        let (child, stopped) = {
+            let sessions = self.sessions.lock().unwrap();
+            let session = sessions.get(&id).context("会话不存在或已结束")?;
+            (session.child.clone(), session.stopped.clone())
+        };
+        stopped.store(true, Ordering::Relaxed);
+        let mut child = child.lock().unwrap();
+        // 已经退出时 kill 会失败，忽略即可
+        if child.try_wait().ok().flatten().is_none() {
+            child.kill().context("停止进程失败")?;
+        }
+        Ok(())
+    }
+
+    /// 停止全部会话，返回停止的数量
+    pub fn stop_all(&self) -> usize {
+        let ids: Vec<u64> = self.sessions.lock().unwrap().keys().copied().collect();
+        ids.into_iter().filter(|id| self.stop(*id).is_ok()).count()
+    }
+}
+
+// 测试用 sh 启动短命令
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+
+    /// 启动短命令并登记，结束事件发送到通道
+    fn spawn(manager: &SessionManager, script: &str, tx: &mpsc::Sender<SessionEnded>) -> SessionInfo {
+        let child = Command::new("sh")
+            .args(["-c", script])
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let tx = tx.clone();
+        manager.register_with(child, "emulator-5554", "mirror", None, &[script.to_string()], move |ended| {
+            let _ = tx.send(ended);
+        })
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let manager = SessionManager::new();
+        let (tx, rx) = mpsc::channel();
+        let timeout = Duration::from_secs(10);
+
+        // 自行退出：保留退出码和 stderr，stopped 为 false
+        let exited = spawn(&manager, "echo failed >&2; exit 3", &tx);
+        let ended = rx.recv_timeout(timeout).unwrap();
+        assert_eq!(ended.session.id, exited.id);
+        assert_eq!(ended.exit_code, Some(3));
+        assert!(!ended.stopped);
+        assert_eq!(ended.stderr, "failed");
+        assert!(manager.list().is_empty());
+
+        // stop 停止一个会话，stop_all 停止其余的
+        let first = spawn(&manager, "exec sleep 30", &tx);
+        let second = spawn(&manager, "exec sleep 30", &tx);
+        let third = spawn(&manager, "exec sleep 30", &tx);
+        let ids: Vec<u64> = manager.list().iter().map(|s| s.id).collect();
+        assert_eq!(ids, [first.id, second.id, third.id]);
+
+        manager.stop(first.id).unwrap();
+        let ended = rx.recv_timeout(timeout).unwrap();
+        assert_eq!(ended.session.id, first.id);
+        assert!(ended.stopped);
+        assert_eq!(manager.list().len(), 2);
+
+        assert_eq!(manager.stop_all(), 2);
+        let mut ended: Vec<SessionEnded> = (0..2).map(|_| rx.recv_timeout(timeout).unwrap()).collect();
+        ended.sort_by_key(|e| e.session.id);
+        assert_eq!(ended.iter().map(|e| e.session.id).collect::<Vec<_>>(), [second.id, third.id]);
+        assert!(ended.iter().all(|e| e.stopped && e.exit_code.is_none()));
+        assert!(manager.list().is_empty());
+
+        // 已结束的会话不能再停止
+        assert!(manager.stop(first.id).is_err());
+        assert_eq!(manager.stop_all(), 0);
+    }
+}
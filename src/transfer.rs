@@ -0,0 +1,457 @@
+//! Scrcpy Launcher - 文件传输管理模块
+//!
+//! 为每次 push/pull 分配传输 ID，统一处理：
+//! - 排队与并发限制
+//! - 取消 (断开 SYNC 连接)
+//! - 失败重试
+//! - 进度事件节流 (速率、剩余时间)
+//! - 已结束传输的历史记录
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use tokio::sync::Semaphore;
+use crate::adb_sync::{AdbPusher, CancelToken, ProgressCallback, PushFailure, TransferCancelled, TransferStats};
+
+/// 进度事件的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// 保留的历史记录条数
+const HISTORY_LIMIT: usize = 100;
+
+/// 传输方向
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferKind {
+    Push,
+    Pull,
+}
+
+/// 传输状态
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferStatus {
+    Queued,
+    Running,
+    /// 失败后等待重试
+    Retrying,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl TransferStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, TransferStatus::Completed | TransferStatus::Failed | TransferStatus::Cancelled)
+    }
+}
+
+/// 传输请求
+#[derive(Clone, Debug)]
+pub struct TransferRequest {
+    pub kind: TransferKind,
+    /// 目标设备序列号，None 表示唯一连接的设备
+    pub serial: Option<String>,
+    pub local_path: String,
+    pub remote_path: String,
+    /// 仅 push 使用的权限覆盖
+    pub mode: Option<u32>,
+}
+
+/// 传输信息 (用于列表、历史和状态事件)
+#[derive(Serialize, Clone, Debug)]
+pub struct TransferInfo {
+    pub id: u64,
+    pub kind: TransferKind,
+    pub serial: Option<String>,
+    pub local_path: String,
+    pub remote_path: String,
+    pub status: TransferStatus,
+    pub attempts: u32,
+    pub bytes: u64,
+    pub total: u64,
+    pub error: Option<String>,
+    pub stats: Option<TransferStats>,
+    /// 目录推送中失败的文件
+    pub failures: Vec<PushFailure>,
+    /// 创建时间 (Unix 毫秒)
+    pub created_at: u64,
+    /// 结束时间 (Unix 毫秒)
+    pub finished_at: Option<u64>,
+}
+
+/// 进度事件 (`transfer-progress`)
+#[derive(Serialize, Clone, Debug)]
+pub struct TransferProgress {
+    pub id: u64,
+    pub kind: TransferKind,
+    pub bytes: u64,
+    pub total: u64,
+    pub progress: u32,
+    /// 当前速率 (字节/秒)
+    pub rate: f64,
+    /// 预计剩余秒数，速率未知时为 None
+    pub eta_secs: Option<f64>,
+}
+
+/// 额外的进度观察者 (收到的是已节流的进度)
+pub type ProgressObserver = Arc<dyn Fn(&TransferProgress) + Send + Sync>;
+
+struct Entry {
+    info: TransferInfo,
+    cancel: CancelToken,
+}
+
+struct Inner {
+    next_id: AtomicU64,
+    semaphore: Arc<Semaphore>,
+    concurrency: Mutex<usize>,
+    max_retries: AtomicU32,
+    active: Mutex<IndexMap<u64, Entry>>,
+    history: Mutex<VecDeque<TransferInfo>>,
+}
+
+/// 传输管理器 (保存在 Tauri State 中)
+#[derive(Clone)]
+pub struct TransferManager {
+    inner: Arc<Inner>,
+}
+
+impl TransferManager {
+    pub fn new(concurrency: u32, max_retries: u32) -> Self {
+        let concurrency = concurrency.max(1) as usize;
+        Self {
+            inner: Arc::new(Inner {
+                next_id: AtomicU64::new(1),
+                semaphore: Arc::new(Semaphore::new(concurrency)),
+                concurrency: Mutex::new(concurrency),
+                max_retries: AtomicU32::new(max_retries),
+                active: Mutex::new(IndexMap::new()),
+                history: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    /// 调整并发数，正在进行的传输不受影响
+    pub fn set_concurrency(&self, concurrency: u32) {
+        let target = concurrency.max(1) as usize;
+        let mut current = self.inner.concurrency.lock().unwrap();
+        if target > *current {
+            self.inner.semaphore.add_permits(target - *current);
+        } else if target < *current {
+            // 等占用中的许可释放后再回收多余的许可
+            let semaphore = self.inner.semaphore.clone();
+            let excess = (*current - target) as u32;
+            tauri::async_runtime::spawn(async move {
+                if let Ok(permits) = semaphore.acquire_many(excess).await {
+                    permits.forget();
+                }
+            });
+        }
+        *current = target;
+    }
+
+    pub fn set_max_retries(&self, max_retries: u32) {
+        self.inner.max_retries.store(max_retries, Ordering::Relaxed);
+    }
+
+    /// 提交传输，返回传输 ID 和可等待最终结果的任务句柄
+    pub fn submit(
+        &self,
+        app: tauri::AppHandle,
+        request: TransferRequest,
+        observer: Option<ProgressObserver>,
+    ) -> (u64, tauri::async_runtime::JoinHandle<TransferInfo>) {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancel = CancelToken::new();
+        let info = TransferInfo {
+            id,
+            kind: request.kind,
+            serial: request.serial.clone(),
+            local_path: request.local_path.clone(),
+            remote_path: request.remote_path.clone(),
+            status: TransferStatus::Queued,
+            attempts: 0,
+            bytes: 0,
+            total: 0,
+            error: None,
+            stats: None,
+            failures: Vec::new(),
+            created_at: now_millis(),
+            finished_at: None,
+        };
+        self.inner.active.lock().unwrap().insert(id, Entry { info: info.clone(), cancel: cancel.clone() });
+        let _ = app.emit("transfer-status", &info);
+
+        let manager = self.clone();
+        let handle = tauri::async_runtime::spawn(async move { manager.run(app, id, request, cancel, observer).await });
+        (id, handle)
+    }
+
+    /// 取消传输 (排队中或进行中)，传输不存在或已结束时返回 false
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.inner.active.lock().unwrap().get(&id) {
+            Some(entry) => {
+                entry.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 排队中和进行中的传输
+    pub fn list(&self) -> Vec<TransferInfo> {
+        self.inner.active.lock().unwrap().values().map(|e| e.info.clone()).collect()
+    }
+
+    /// 已结束的传输，最新的在前
+    pub fn history(&self) -> Vec<TransferInfo> {
+        self.inner.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear_history(&self) {
+        self.inner.history.lock().unwrap().clear();
+    }
+
+    async fn run(
+        &self,
+        app: tauri::AppHandle,
+        id: u64,
+        request: TransferRequest,
+        cancel: CancelToken,
+        observer: Option<ProgressObserver>,
+    ) -> TransferInfo {
+        // 1. 排队等待并发许可
+        let semaphore = self.inner.semaphore.clone();
+        let _permit = tokio::select! {
+            permit = semaphore.acquire_owned() => match permit {
+                Ok(permit) => permit,
+                Err(_) => return self.finish(&app, id, TransferStatus::Failed, Some("传输管理器已关闭".to_string())),
+            },
+            _ = cancel.cancelled() => return self.finish(&app, id, TransferStatus::Cancelled, None),
+        };
+
+        // 2. 执行，失败时按退避重试
+        loop {
+            let attempt = self.update(&app, id, |info| {
+                info.status = TransferStatus::Running;
+                info.attempts += 1;
+                info.error = None;
+            }).map_or(1, |info| info.attempts);
+
+            let progress = self.progress_callback(&app, id, request.kind, observer.clone());
+            let pusher = AdbPusher::new(request.serial.clone()).with_cancel(cancel.clone());
+            let result = execute(&pusher, &request, progress).await;
+
+            match result {
+                Ok(outcome) => {
+                    let failed = !outcome.failures.is_empty();
+                    self.update(&app, id, |info| {
+                        info.bytes = outcome.stats.bytes;
+                        info.total = info.total.max(outcome.stats.bytes);
+                        info.stats = Some(outcome.stats);
+                        info.failures = outcome.failures;
+                    });
+                    // 目录中个别文件失败不整体重试，失败列表记录在 failures 中
+                    return if failed {
+                        self.finish(&app, id, TransferStatus::Failed, Some("部分文件传输失败".to_string()))
+                    } else {
+                        self.finish(&app, id, TransferStatus::Completed, None)
+                    };
+                }
+                Err(e) if e.is::<TransferCancelled>() => {
+                    return self.finish(&app, id, TransferStatus::Cancelled, None);
+                }
+                Err(e) if attempt <= self.inner.max_retries.load(Ordering::Relaxed) => {
+                    self.update(&app, id, |info| {
+                        info.status = TransferStatus::Retrying;
+                        info.error = Some(e.to_string());
+                    });
+                    let backoff = Duration::from_secs(attempt as u64);
+                    tokio::select! {
+                        _ = tokio::time::sleep(backoff) => {}
+                        _ = cancel.cancelled() => return self.finish(&app, id, TransferStatus::Cancelled, None),
+                    }
+                }
+                Err(e) => {
+                    return self.finish(&app, id, TransferStatus::Failed, Some(e.to_string()));
+                }
+            }
+        }
+    }
+
+    /// 修改进行中的传输信息并发送状态事件
+    fn update(&self, app: &tauri::AppHandle, id: u64, f: impl FnOnce(&mut TransferInfo)) -> Option<TransferInfo> {
+        let info = {
+            let mut active = self.inner.active.lock().unwrap();
+            let entry = active.get_mut(&id)?;
+            f(&mut entry.info);
+            entry.info.clone()
+        };
+        let _ = app.emit("transfer-status", &info);
+        Some(info)
+    }
+
+    /// 结束传输：移入历史并发送最终状态
+    fn finish(&self, app: &tauri::AppHandle, id: u64, status: TransferStatus, error: Option<String>) -> TransferInfo {
+        debug_assert!(status.is_finished());
+        let mut info = self.inner.active.lock().unwrap()
+            .shift_remove(&id)
+            .map(|entry| entry.info)
+            .expect("每个传输只会结束一次");
+        info.status = status;
+        info.error = error;
+        info.finished_at = Some(now_millis());
+
+        let mut history = self.inner.history.lock().unwrap();
+        history.push_front(info.clone());
+        history.truncate(HISTORY_LIMIT);
+        drop(history);
+
+        let _ = app.emit("transfer-status", &info);
+        info
+    }
+
+    /// 创建带节流的进度回调
+    fn progress_callback(
+        &self,
+        app: &tauri::AppHandle,
+        id: u64,
+        kind: TransferKind,
+        observer: Option<ProgressObserver>,
+    ) -> ProgressCallback {
+        let manager = self.clone();
+        let app = app.clone();
+        let throttle = Mutex::new(ProgressThrottle::new());
+
+        Box::new(move |bytes, total| {
+            let Some(progress) = throttle.lock().unwrap().sample(id, kind, bytes, total, Instant::now()) else {
+                return;
+            };
+            if let Some(entry) = manager.inner.active.lock().unwrap().get_mut(&id) {
+                entry.info.bytes = bytes;
+                entry.info.total = total;
+            }
+            let _ = app.emit("transfer-progress", &progress);
+            if let Some(observer) = &observer {
+                observer(&progress);
+            }
+        })
+    }
+}
+
+/// 单次执行的结果
+struct Outcome {
+    stats: TransferStats,
+    failures: Vec<PushFailure>,
+}
+
+async fn execute(pusher: &AdbPusher, request: &TransferRequest, progress: ProgressCallback) -> anyhow::Result<Outcome> {
+    match request.kind {
+        TransferKind::Push if Path::new(&request.local_path).is_dir() => {
+            let summary = pusher.push_dir(&request.local_path, &request.remote_path, request.mode, Some(progress)).await?;
+            Ok(Outcome { stats: summary.stats, failures: summary.failures })
+        }
+        TransferKind::Push => {
+            let stats = pusher.push(&request.local_path, &request.remote_path, request.mode, Some(progress)).await?;
+            Ok(Outcome { stats, failures: Vec::new() })
+        }
+        TransferKind::Pull => {
+            let stats = pusher.pull(&request.remote_path, &request.local_path, Some(progress)).await?;
+            Ok(Outcome { stats, failures: Vec::new() })
+        }
+    }
+}
+
+/// 进度节流与速率估算
+struct ProgressThrottle {
+    last_emit: Option<Instant>,
+    last_bytes: u64,
+    /// 指数平滑后的速率 (字节/秒)
+    rate: f64,
+}
+
+impl ProgressThrottle {
+    fn new() -> Self {
+        Self { last_emit: None, last_bytes: 0, rate: 0.0 }
+    }
+
+    /// 距上次输出超过间隔或传输完成时返回进度，否则返回 None
+    fn sample(&mut self, id: u64, kind: TransferKind, bytes: u64, total: u64, now: Instant) -> Option<TransferProgress> {
+        let done = total > 0 && bytes >= total;
+        let elapsed = match self.last_emit {
+            Some(last) => now.duration_since(last),
+            // 第一次只记录起点
+            None => {
+                self.last_emit = Some(now);
+                self.last_bytes = bytes;
+                if !done {
+                    return None;
+                }
+                Duration::ZERO
+            }
+        };
+        if elapsed < PROGRESS_INTERVAL && !done {
+            return None;
+        }
+
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            let instant_rate = bytes.saturating_sub(self.last_bytes) as f64 / secs;
+            self.rate = if self.rate == 0.0 { instant_rate } else { self.rate * 0.7 + instant_rate * 0.3 };
+        }
+        self.last_emit = Some(now);
+        self.last_bytes = bytes;
+
+        let eta_secs = if self.rate > 0.0 && total >= bytes {
+            Some((total - bytes) as f64 / self.rate)
+        } else {
+            None
+        };
+        Some(TransferProgress {
+            id,
+            kind,
+            bytes,
+            total,
+            progress: if total > 0 { (bytes as f64 / total as f64 * 100.0) as u32 } else { 0 },
+            rate: self.rate,
+            eta_secs,
+        })
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_throttle() {
+        let start = Instant::now();
+        let mut throttle = ProgressThrottle::new();
+
+        // 起点和间隔内的进度被丢弃
+        assert!(throttle.sample(1, TransferKind::Push, 0, 1000, start).is_none());
+        assert!(throttle.sample(1, TransferKind::Push, 100, 1000, start + Duration::from_millis(100)).is_none());
+
+        let p = throttle.sample(1, TransferKind::Push, 500, 1000, start + Duration::from_millis(500)).unwrap();
+        assert_eq!(p.progress, 50);
+        assert!((p.rate - 1000.0).abs() < 1e-6);
+        assert!((p.eta_secs.unwrap() - 0.5).abs() < 1e-6);
+
+        // 完成时不受间隔限制
+        let p = throttle.sample(1, TransferKind::Push, 1000, 1000, start + Duration::from_millis(510)).unwrap();
+        assert_eq!(p.progress, 100);
+        assert_eq!(p.eta_secs, Some(0.0));
+    }
+}
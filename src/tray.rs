@@ -3,49 +3,98 @@
 use tauri::{
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     menu::{Menu, MenuItem, PredefinedMenuItem},
-    Manager, Runtime,
+    AppHandle, Emitter, Manager, Runtime,
 };
+use crate::adb_host::{AdbDevice, DeviceState};
 use crate::config::Config;
 
-/// 设置系统托盘
-pub fn setup_tray<R: Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::error::Error>> {
+/// 托盘图标 ID
+const TRAY_ID: &str = "main";
+
+/// 设备在托盘菜单中显示的文字
+fn device_label(device: &AdbDevice) -> String {
+    let state = match device.state {
+        DeviceState::Device => return device.display_name(),
+        DeviceState::Unauthorized => "未授权",
+        DeviceState::Offline => "离线",
+        DeviceState::Authorizing | DeviceState::Connecting => "连接中",
+        _ => "不可用",
+    };
+    format!("{} ({})", device.display_name(), state)
+}
+
+/// 托盘提示文字
+fn tooltip(devices: &[AdbDevice]) -> String {
+    let ready: Vec<String> = devices.iter().filter(|d| d.is_ready()).map(|d| d.display_name()).collect();
+    if ready.is_empty() {
+        "Scrcpy Launcher - 未连接设备".to_string()
+    } else {
+        format!("Scrcpy Launcher\n已连接: {}", ready.join(", "))
+    }
+}
+
+/// 构建托盘菜单，设备列表显示在最上方，无可用设备时禁用启动项
+fn build_menu<R: Runtime, M: Manager<R>>(manager: &M, devices: &[AdbDevice]) -> tauri::Result<Menu<R>> {
     let config = Config::load();
-    
+    let has_device = devices.iter().any(|d| d.is_ready());
+
     // 创建菜单项
-    let show_item = MenuItem::with_id(app, "show", "显示主窗口", true, None::<&str>)?;
-    let hide_item = MenuItem::with_id(app, "hide", "隐藏到托盘", true, None::<&str>)?;
-    let separator = PredefinedMenuItem::separator(app)?;
-    
+    let show_item = MenuItem::with_id(manager, "show", "显示主窗口", true, None::<&str>)?;
+    let hide_item = MenuItem::with_id(manager, "hide", "隐藏到托盘", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(manager)?;
+
+    // 设备列表，点击可用设备将其设为当前设备
+    let device_items: Vec<MenuItem<R>> = if devices.is_empty() {
+        vec![MenuItem::with_id(manager, "device:", "未连接设备", false, None::<&str>)?]
+    } else {
+        devices
+            .iter()
+            .filter_map(|d| {
+                let label = if config.active_device.as_deref() == Some(d.serial.as_str()) {
+                    format!("✓ {}", device_label(d))
+                } else {
+                    device_label(d)
+                };
+                MenuItem::with_id(manager, format!("device:{}", d.serial), label, d.is_ready(), None::<&str>).ok()
+            })
+            .collect()
+    };
+
     // 创建快捷操作
-    let mirror_item = MenuItem::with_id(app, "mirror", "屏幕镜像", true, None::<&str>)?;
-    let audio_item = MenuItem::with_id(app, "audio", "纯音频", true, None::<&str>)?;
-    
-    let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-    
+    let mirror_item = MenuItem::with_id(manager, "mirror", "屏幕镜像", has_device, None::<&str>)?;
+    let audio_item = MenuItem::with_id(manager, "audio", "纯音频", has_device, None::<&str>)?;
+
+    let quit_item = MenuItem::with_id(manager, "quit", "退出", true, None::<&str>)?;
+
     // 构建菜单
     let mut menu_items: Vec<&dyn tauri::menu::IsMenuItem<R>> = Vec::new();
-    
+
+    for item in &device_items {
+        menu_items.push(item);
+    }
+    menu_items.push(&separator);
+
     // 添加应用快捷方式
     let apps = config.apps.clone();
     let app_count = config.tray_app_count as usize;
-    
+
     // 注意: 由于 Rust 的所有权规则，我们需要在这里创建应用菜单项
     let app_menu_items: Vec<MenuItem<R>> = apps
         .iter()
         .take(app_count)
         .filter_map(|(pkg, app_config)| {
-            MenuItem::with_id(app, &format!("app:{}", pkg), &app_config.name, true, None::<&str>).ok()
+            MenuItem::with_id(manager, format!("app:{}", pkg), &app_config.name, has_device, None::<&str>).ok()
         })
         .collect();
-    
+
     for item in &app_menu_items {
         menu_items.push(item);
     }
-    
+
     if !app_menu_items.is_empty() {
         menu_items.push(&separator);
     }
-    
+
     // 添加镜像和音频选项
     if config.tray_show_mirror {
         menu_items.push(&mirror_item);
@@ -56,18 +105,33 @@ pub fn setup_tray<R: Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::er
     if config.tray_show_mirror || config.tray_show_audio {
         menu_items.push(&separator);
     }
-    
+
     menu_items.push(&show_item);
     menu_items.push(&hide_item);
     menu_items.push(&separator);
     menu_items.push(&quit_item);
-    
-    let menu = Menu::with_items(app, &menu_items)?;
-    
+
+    Menu::with_items(manager, &menu_items)
+}
+
+/// 根据最新的设备列表刷新托盘提示和菜单
+pub fn update_devices<R: Runtime>(app: &AppHandle<R>, devices: &[AdbDevice]) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip(devices)));
+        if let Ok(menu) = build_menu(app, devices) {
+            let _ = tray.set_menu(Some(menu));
+        }
+    }
+}
+
+/// 设置系统托盘
+pub fn setup_tray<R: Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::error::Error>> {
+    let menu = build_menu(app, &[])?;
+
     // 创建托盘图标
-    let _tray = TrayIconBuilder::new()
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(app.default_window_icon().unwrap().clone())
-        .tooltip("Scrcpy Launcher")
+        .tooltip(tooltip(&[]))
         .menu(&menu)
         .show_menu_on_left_click(false)
         .on_menu_event(move |app, event| {
@@ -83,21 +147,25 @@ pub fn setup_tray<R: Runtime>(app: &tauri::App<R>) -> Result<(), Box<dyn std::er
                     let _ = window.hide();
                 }
             } else if id == "mirror" {
-                let _ = crate::scrcpy::launch_scrcpy(crate::scrcpy::LaunchMode::Mirror);
+                let _ = crate::scrcpy::launch_scrcpy(app, crate::scrcpy::LaunchMode::Mirror, None);
             } else if id == "audio" {
-                let _ = crate::scrcpy::launch_scrcpy(crate::scrcpy::LaunchMode::Audio);
+                let _ = crate::scrcpy::launch_scrcpy(app, crate::scrcpy::LaunchMode::Audio, None);
             } else if id == "quit" {
-                crate::scrcpy::cleanup_before_exit();
+                crate::scrcpy::cleanup_before_exit(app);
                 app.exit(0);
+            } else if let Some(serial) = id.strip_prefix("device:") {
+                crate::scrcpy::set_active_device(Some(serial.to_string()));
+                update_devices(app, &crate::scrcpy::list_devices().unwrap_or_default());
+                let _ = app.emit("active-device-changed", serial);
             } else if id.starts_with("app:") {
                 let package = id.strip_prefix("app:").unwrap_or("");
                 let config = Config::load();
                 if let Some(app_config) = config.apps.get(package) {
-                    let _ = crate::scrcpy::launch_scrcpy(crate::scrcpy::LaunchMode::App {
+                    let _ = crate::scrcpy::launch_scrcpy(app, crate::scrcpy::LaunchMode::App {
                         package: package.to_string(),
                         settings: app_config.settings.clone(),
                         scrcpy_args: app_config.scrcpy_args.clone(),
-                    });
+                    }, None);
                 }
             }
         })
@@ -7,6 +7,7 @@ fn test_adb_status_structure() {
     let status = scrcpy::AdbStatus {
         connected: false,
         message: "Test".to_string(),
+        devices: Vec::new(),
     };
     assert_eq!(status.connected, false);
     assert_eq!(status.message, "Test");